@@ -3,24 +3,29 @@ extern crate citrine;
 extern crate specs;
 
 use specs::{Dispatcher, World};
-use citrine::class::{AbsolutePosition, Viewport, Children, Coordinate, Parent, Position, Display, Bounds};
+use citrine::layout::Insets;
+use citrine::name::NameIndex;
+use citrine::class::{AbsolutePosition, Anchor, Viewport, UiScale, ScaleMode, Coordinate, Parent, Position, PositionKind, Display, Sides, Bounds, Name, SafeArea, Text};
 
 fn main() {
     let mut dispatcher = citrine::dispatcher();
     let mut world = World::new();
-    world.add_resource::<Viewport>(Viewport {
-        width: 1920,
-        height: 1080,
-    });
-    world.register::<Parent>();
-    world.register::<Display>();
-    world.register::<Children>();
-    world.register::<Position>();
-    world.register::<AbsolutePosition>();
-    world.register::<Bounds>();
+    citrine::register(&mut world);
+    world.add_resource::<Viewport>(Viewport::new(1920, 1080)
+        .with_scale_factor(1.25)
+        .with_safe_area(Insets {
+            top: 48.0,
+            bottom: 48.0,
+            left: 96.0,
+            right: 96.0,
+        }));
+    world.add_resource::<UiScale>(UiScale::new(1920.0, 1080.0, ScaleMode::Fit));
 
     let entity = world.create_entity()
+        .with(Name("main".to_owned()))
+        .with(SafeArea)
         .with(Position {
+            kind: PositionKind::Absolute,
             x: Some(Coordinate::Pixel(50.0)),
             y: Some(Coordinate::Percent(0.5)),
             .. Position::default()
@@ -29,15 +34,53 @@ fn main() {
         .with(Bounds {
             width: Some(Coordinate::Percent(1.0)), 
             height: Some(Coordinate::Percent(0.01)), 
+            min_height: Some(Coordinate::Pixel(120.0)),
+            .. Bounds::default()
         })
         .build();
 
     let child_entity = world.create_entity()
+        .with(Parent { entity: entity })
         .with(Position::default())
         .with(AbsolutePosition::default())
         .with(Bounds {
-            width: Some(Coordinate::Percent(0.70)), 
+            width: Some(Coordinate::Percent(0.70) - Coordinate::Pixel(20.0)),
             height: Some(Coordinate::Pixel(100.0)), 
+            max_width: Some(Coordinate::Pixel(1200.0)),
+            .. Bounds::default()
+        })
+        .with(Display {
+            padding: Sides::all(Coordinate::Pixel(8.0)),
+            .. Display::default()
+        })
+        .build();
+
+    let label = world.create_entity()
+        .with(Name("label".to_owned()))
+        .with(Parent { entity: child_entity })
+        .with(Position::default())
+        .with(AbsolutePosition::default())
+        .with(Text {
+            text: "Sized to fit".to_owned(),
+            size: 16.0,
+        })
+        .build();
+
+    let badge = world.create_entity()
+        .with(Parent { entity: child_entity })
+        .with(Position {
+            kind: PositionKind::Free,
+            x: Some(Coordinate::Pixel(-16.0)),
+            y: Some(Coordinate::Pixel(-16.0)),
+            anchor: Anchor::bottom_right(),
+            pivot: Anchor::bottom_right(),
+            .. Position::default()
+        })
+        .with(AbsolutePosition::default())
+        .with(Bounds {
+            width: Some(Coordinate::Em(1.5)),
+            height: Some(Coordinate::Em(1.5)),
+            .. Bounds::default()
         })
         .build();
 
@@ -47,4 +90,10 @@ fn main() {
     world.maintain();
     dispatcher.dispatch(&mut world.res);
     world.maintain();
+
+    println!("main/label = {:?}", world.read_resource::<NameIndex>().find("main/label"));
+
+    let viewport = world.read_resource::<Viewport>();
+    let badge = world.read::<AbsolutePosition>().get(badge).map(|absolute| absolute.physical_snapped(&viewport));
+    println!("badge in physical pixels = {:?}", badge);
 }
@@ -1,60 +1,562 @@
 
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::cmp;
+use std::f32;
+use std::ops::{Add, Mul, Sub};
 
-use specs::{Component, FlaggedStorage, DenseVecStorage, Entity};
-//use ::track::TrackStorage;
+use specs::{Component, DenseVecStorage, Entity};
+use track::TrackStorage;
+
+use layout::{Insets, Rect, Size};
 
 macro_rules! define_component {
     ( $ident:ident ) => {
         impl Component for $ident {
-            type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+            type Storage = TrackStorage<Self, DenseVecStorage<Self>>;
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// The screen the UI is shown on.
+///
+/// `width` and `height` are in physical pixels, the ones of the window. Layout happens in
+/// logical pixels, which are `scale_factor` physical pixels each, so the UI keeps its size
+/// on screens with a higher density.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Viewport {
     pub width: u32,
     pub height: u32,
+    pub scale_factor: f32,
+    /// Physical pixels cut off on each side, by TV overscan or a notch, see `SafeArea`.
+    pub safe_area: Insets,
+}
+
+impl Viewport {
+    /// Viewport of `width` by `height` physical pixels, with a scale factor of 1 and
+    /// nothing cut off.
+    pub fn new(width: u32, height: u32) -> Self {
+        Viewport {
+            width: width,
+            height: height,
+            scale_factor: 1.0,
+            safe_area: Insets::default(),
+        }
+    }
+
+    /// Sets the amount of physical pixels in a logical one, which has to be positive.
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        assert!(scale_factor > 0.0, "scale factor has to be positive, got {}", scale_factor);
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    pub fn with_safe_area(mut self, safe_area: Insets) -> Self {
+        self.safe_area = safe_area;
+        self
+    }
+
+    /// Width in logical pixels.
+    pub fn logical_width(&self) -> f32 {
+        self.width as f32 / self.scale()
+    }
+
+    /// Height in logical pixels.
+    pub fn logical_height(&self) -> f32 {
+        self.height as f32 / self.scale()
+    }
+
+    /// The `scale_factor`, kept positive in case it was set directly.
+    pub(crate) fn scale(&self) -> f32 {
+        self.scale_factor.max(f32::EPSILON)
+    }
 }
 
+/// How the reference resolution of a `UiScale` is mapped onto the `Viewport`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScaleMode {
+    /// A `Pixel` is always a logical pixel, the reference resolution is ignored.
+    ConstantPixelSize,
+    /// Scales so the whole reference resolution fits inside of the viewport.
+    Fit,
+    /// Scales so the reference resolution covers the whole viewport.
+    Fill,
+    /// Scales so the reference width is the width of the viewport.
+    MatchWidth,
+    /// Scales so the reference height is the height of the viewport.
+    MatchHeight,
+}
+
+/// Resource for authoring the UI at a reference resolution, like 1920x1080.
+///
+/// Every `Coordinate::Pixel` is scaled by the `factor` from the reference resolution to
+/// the logical size of the `Viewport`. Other units are left alone. `citrine::register`
+/// adds the default one, which doesn't scale anything.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UiScale {
+    pub reference: Size,
+    pub mode: ScaleMode,
+}
+
+impl UiScale {
+    pub fn new(width: f32, height: f32, mode: ScaleMode) -> Self {
+        UiScale {
+            reference: Size {
+                width: width,
+                height: height,
+            },
+            mode: mode,
+        }
+    }
+
+    /// Amount of logical pixels of `viewport` a `Pixel` is.
+    pub fn factor(&self, viewport: &Viewport) -> f32 {
+        if self.reference.width <= 0.0 || self.reference.height <= 0.0 {
+            return 1.0;
+        }
+
+        let width = viewport.logical_width() / self.reference.width;
+        let height = viewport.logical_height() / self.reference.height;
+        match self.mode {
+            ScaleMode::ConstantPixelSize => 1.0,
+            ScaleMode::Fit => width.min(height),
+            ScaleMode::Fill => width.max(height),
+            ScaleMode::MatchWidth => width,
+            ScaleMode::MatchHeight => height,
+        }
+    }
+}
+
+impl Default for UiScale {
+    fn default() -> Self {
+        UiScale::new(0.0, 0.0, ScaleMode::ConstantPixelSize)
+    }
+}
+
+/// Lays out an element inside of the safe area of the `Viewport` instead of the whole
+/// screen.
+///
+/// Only roots and `Absolute` elements are placed against the screen, so that's where it
+/// makes a difference. Roots with it are laid out together, apart from the other roots.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SafeArea;
+
 /// Describes the layout for this ui element.
 ///
-/// All children will behave by these.
-pub struct Layout(pub Box<Any + Send + Sync>);
+/// All children will behave by these. The `Layouts` resource decides which algorithm
+/// lays out which type of layout, like `Grid` or `Stack`.
+pub struct Layout {
+    kind: TypeId,
+    layout: Box<Any + Send + Sync>,
+}
+
+impl Layout {
+    pub fn new<L: Any + Send + Sync>(layout: L) -> Self {
+        Layout {
+            kind: TypeId::of::<L>(),
+            layout: Box::new(layout),
+        }
+    }
+
+    /// Type of the layout inside.
+    pub fn kind(&self) -> TypeId {
+        self.kind
+    }
+
+    /// Gets the layout if it is a `L`.
+    pub fn get<L: Any>(&self) -> Option<&L> {
+        let layout: &Any = &*self.layout;
+        layout.downcast_ref::<L>()
+    }
+
+    /// Gets the layout if it is a `L`.
+    pub fn get_mut<L: Any>(&mut self) -> Option<&mut L> {
+        let layout: &mut Any = &mut *self.layout;
+        layout.downcast_mut::<L>()
+    }
+}
+
+/// Settings of this ui element for the `Layout` of its parent, like a `GridCell` or
+/// `Flex`.
+pub struct LayoutItem {
+    item: Box<Any + Send + Sync>,
+}
+
+impl LayoutItem {
+    pub fn new<T: Any + Send + Sync>(item: T) -> Self {
+        LayoutItem {
+            item: Box::new(item),
+        }
+    }
+
+    /// Gets the settings if they are a `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        let item: &Any = &*self.item;
+        item.downcast_ref::<T>()
+    }
+
+    /// Gets the settings if they are a `T`.
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        let item: &mut Any = &mut *self.item;
+        item.downcast_mut::<T>()
+    }
+}
+
+/// Layout that places the children from the top to the bottom, at the size their
+/// `Bounds` give them.
+///
+/// This is what elements without a `Layout` use.
+#[derive(Clone, Debug, Default)]
+pub struct Flow;
 
+/// Layout that places the children into the cells of a grid.
+///
+/// Children pick their cell with a `GridCell`, otherwise they take the next free one.
+#[derive(Clone, Debug, Default)]
 pub struct Grid {
-    columns: Option<usize>,
-    column_stretch: Vec<f32>,
-    rows: Option<usize>,
-    row_stretch: Vec<f32>,
+    /// Amount of columns, defaults to enough columns for the `column_stretch` and the
+    /// `GridCell`s of the children.
+    pub columns: Option<usize>,
+    /// How much of the width each column gets relative to the others, columns missing
+    /// from here get `1.0`.
+    pub column_stretch: Vec<f32>,
+    /// Amount of rows, defaults to enough rows for the `row_stretch` and all of the
+    /// children.
+    pub rows: Option<usize>,
+    /// How much of the height each row gets relative to the others, rows missing from
+    /// here get `1.0`.
+    pub row_stretch: Vec<f32>,
+    /// Space between columns, `Percent` is based on the width of the grid.
+    pub column_gap: Coordinate,
+    /// Space between rows, `Percent` is based on the height of the grid.
+    pub row_gap: Coordinate,
+}
+
+/// Cell of the parent's `Grid` this UI element goes in, goes in a `LayoutItem`.
+///
+/// Elements without a `width`/`height` in their `Bounds` fill the whole cell.
+#[derive(Clone, Debug)]
+pub struct GridCell {
+    pub column: usize,
+    pub row: usize,
+    /// Amount of columns the cell covers.
+    pub column_span: usize,
+    /// Amount of rows the cell covers.
+    pub row_span: usize,
+}
+
+impl Default for GridCell {
+    fn default() -> Self {
+        GridCell {
+            column: 0,
+            row: 0,
+            column_span: 1,
+            row_span: 1,
+        }
+    }
 }
 
+/// Layout that places the children one after another along a row or a column.
+///
+/// Children can grow into or shrink out of the stack with a `Flex`.
 #[derive(Clone, Debug, Default)]
-pub struct Display {
-    pub top: f32,
-    pub bottom: f32,
-    pub left: f32,
-    pub right: f32,
+pub struct Stack {
+    /// Direction the children go in.
+    pub direction: Direction,
+    /// How children are spread out along the direction of the stack.
+    pub justify: Justify,
+    /// How children are placed across the direction of the stack.
+    pub align: Align,
+    /// Space between children, `Percent` is based on the size of the stack along its
+    /// direction.
+    pub gap: Coordinate,
+}
+
+/// Direction of a `Stack`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// Left to right.
+    Row,
+    /// Top to bottom.
+    Column,
 }
 
+impl Default for Direction {
+    fn default() -> Self {
+        Direction::Column
+    }
+}
+
+/// How the children of a `Stack` are spread out along its direction when there is
+/// space left over.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Justify {
+    /// Packed at the start.
+    Start,
+    /// Packed at the end.
+    End,
+    /// Packed in the middle.
+    Center,
+    /// First child at the start, last child at the end and the space split between them.
+    SpaceBetween,
+    /// Every child gets the same space on both of its sides.
+    SpaceAround,
+    /// The space between every child and the edges is the same.
+    SpaceEvenly,
+}
+
+impl Default for Justify {
+    fn default() -> Self {
+        Justify::Start
+    }
+}
+
+/// How the children of a `Stack` are placed across its direction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Align {
+    Start,
+    End,
+    Center,
+    /// Children without a size across the stack fill it.
+    Stretch,
+}
+
+impl Default for Align {
+    fn default() -> Self {
+        Align::Stretch
+    }
+}
+
+/// How a child of a `Stack` grows into free space and shrinks when there isn't enough,
+/// goes in a `LayoutItem`.
 #[derive(Clone, Debug)]
+pub struct Flex {
+    /// Share of the free space this element gets, `0.0` keeps it at its size.
+    pub grow: f32,
+    /// Share of the missing space this element gives up, weighted by its size.
+    pub shrink: f32,
+}
+
+impl Default for Flex {
+    fn default() -> Self {
+        Flex {
+            grow: 0.0,
+            shrink: 1.0,
+        }
+    }
+}
+
+/// Box model of an element.
+///
+/// The `Bounds` are the border box of the element. `margin` is space kept clear around
+/// it, which layouts treat as part of the element, and `padding` is space inside of it
+/// that the children are kept out of.
+#[derive(Clone, Debug, Default)]
+pub struct Display {
+    pub margin: Sides,
+    pub padding: Sides,
+}
+
+/// Length on each side of an element, `Percent` is based on the width of the parent for
+/// the left and right, and its height for the top and bottom.
+#[derive(Clone, Debug, Default)]
+pub struct Sides {
+    pub top: Coordinate,
+    pub bottom: Coordinate,
+    pub left: Coordinate,
+    pub right: Coordinate,
+}
+
+impl Sides {
+    /// Same length on every side.
+    pub fn all(length: Coordinate) -> Self {
+        Sides {
+            top: length.clone(),
+            bottom: length.clone(),
+            left: length.clone(),
+            right: length,
+        }
+    }
+
+    /// Resolves the sides inside of a parent that is `width` by `height`.
+    pub fn resolve(&self, width: f32, height: f32, units: &Units) -> Insets {
+        Insets {
+            top: self.top.resolve(height, units),
+            bottom: self.bottom.resolve(height, units),
+            left: self.left.resolve(width, units),
+            right: self.right.resolve(width, units),
+        }
+    }
+}
+
+/// Sizes a `Coordinate` can be based on other than its parent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Units {
+    /// Size of the screen, for `ViewportWidth` and `ViewportHeight`.
+    pub viewport: Size,
+    /// Height of the font in pixels, for `Em`, with the `UiScale` applied.
+    pub font_size: f32,
+    /// Size of a `Pixel`, see `UiScale`.
+    pub pixel: f32,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units {
+            viewport: Size::default(),
+            font_size: 16.0,
+            pixel: 1.0,
+        }
+    }
+}
+
+/// Length in the UI.
+///
+/// Coordinates can be combined into a `Calc` with `+`, `-`, `*` and `min`/`max`, so
+/// "the whole parent minus 20 pixels" is `Coordinate::Percent(1.0) - Coordinate::Pixel(20.0)`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum Coordinate {
     /// Coordinate space in pixels.
     Pixel(f32),
     /// Coordinate space in percentage of parent.
     Percent(f32),
+    /// Coordinate space in percentage of the width of the screen.
+    ViewportWidth(f32),
+    /// Coordinate space in percentage of the height of the screen.
+    ViewportHeight(f32),
+    /// Coordinate space in multiples of the font size of the element.
+    ///
+    /// Elements without a `Text` use the font size of their parent.
+    Em(f32),
+    /// Expression combining other coordinates.
+    Calc(Box<Calc>),
+}
+
+/// Expression of a `Coordinate::Calc`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Calc {
+    Add(Coordinate, Coordinate),
+    Sub(Coordinate, Coordinate),
+    Mul(Coordinate, f32),
+    Min(Coordinate, Coordinate),
+    Max(Coordinate, Coordinate),
+}
+
+impl Default for Coordinate {
+    fn default() -> Self {
+        Coordinate::Pixel(0.0)
+    }
+}
+
+impl Coordinate {
+    /// Turns the coordinate into pixels, `Percent` is based on `parent` and the other
+    /// relative units on `units`.
+    pub fn resolve(&self, parent: f32, units: &Units) -> f32 {
+        match *self {
+            Coordinate::Percent(percent) => parent * percent,
+            Coordinate::Pixel(pixel) => pixel * units.pixel,
+            Coordinate::ViewportWidth(percent) => units.viewport.width * percent,
+            Coordinate::ViewportHeight(percent) => units.viewport.height * percent,
+            Coordinate::Em(em) => units.font_size * em,
+            Coordinate::Calc(ref calc) => match **calc {
+                Calc::Add(ref lhs, ref rhs) => lhs.resolve(parent, units) + rhs.resolve(parent, units),
+                Calc::Sub(ref lhs, ref rhs) => lhs.resolve(parent, units) - rhs.resolve(parent, units),
+                Calc::Mul(ref lhs, scale) => lhs.resolve(parent, units) * scale,
+                Calc::Min(ref lhs, ref rhs) => lhs.resolve(parent, units).min(rhs.resolve(parent, units)),
+                Calc::Max(ref lhs, ref rhs) => lhs.resolve(parent, units).max(rhs.resolve(parent, units)),
+            },
+        }
+    }
+
+    /// Whether the coordinate can be resolved without knowing the size of the parent.
+    pub fn is_absolute(&self) -> bool {
+        match *self {
+            Coordinate::Percent(_) => false,
+            Coordinate::Pixel(_) |
+            Coordinate::ViewportWidth(_) |
+            Coordinate::ViewportHeight(_) |
+            Coordinate::Em(_) => true,
+            Coordinate::Calc(ref calc) => match **calc {
+                Calc::Mul(ref lhs, _) => lhs.is_absolute(),
+                Calc::Add(ref lhs, ref rhs) |
+                Calc::Sub(ref lhs, ref rhs) |
+                Calc::Min(ref lhs, ref rhs) |
+                Calc::Max(ref lhs, ref rhs) => lhs.is_absolute() && rhs.is_absolute(),
+            },
+        }
+    }
+
+    /// Whichever of the two coordinates is smaller.
+    pub fn min(self, other: Coordinate) -> Coordinate {
+        Coordinate::Calc(Box::new(Calc::Min(self, other)))
+    }
+
+    /// Whichever of the two coordinates is larger.
+    pub fn max(self, other: Coordinate) -> Coordinate {
+        Coordinate::Calc(Box::new(Calc::Max(self, other)))
+    }
+}
+
+impl Add for Coordinate {
+    type Output = Coordinate;
+    fn add(self, other: Coordinate) -> Coordinate {
+        Coordinate::Calc(Box::new(Calc::Add(self, other)))
+    }
+}
+
+impl Sub for Coordinate {
+    type Output = Coordinate;
+    fn sub(self, other: Coordinate) -> Coordinate {
+        Coordinate::Calc(Box::new(Calc::Sub(self, other)))
+    }
+}
+
+impl Mul<f32> for Coordinate {
+    type Output = Coordinate;
+    fn mul(self, scale: f32) -> Coordinate {
+        Coordinate::Calc(Box::new(Calc::Mul(self, scale)))
+    }
 }
 
 /// Text to be displayed in this segment.
 pub struct Text {
     pub text: String,
+    /// Height of the font in pixels.
+    pub size: f32,
     // TODO: Lots of formatting elements.
     // pub font: String,
-    // pub size: u32,
     // pub wrap: bool,
 }
 
+impl Text {
+    /// Rough size of the text, used for elements that size to their content.
+    ///
+    /// Until there are fonts to measure with, every character is taken to be half as
+    /// wide as the font is high. The font is as big as the `size` in `Pixel`s, so it is
+    /// scaled along with the rest by the `UiScale`.
+    pub fn measure(&self, units: &Units) -> Size {
+        let size = self.size * units.pixel;
+        let columns = self.text.lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let lines = self.text.lines().count();
+
+        Size {
+            width: columns as f32 * size * 0.5,
+            height: lines as f32 * size * 1.2,
+        }
+    }
+}
+
+/// Name of the UI section, used to look it up in the `name::NameIndex`.
+///
+/// Names don't have to be unique, but should be among siblings for paths to find them.
+/// A name shouldn't have a `/` in it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Name(pub String);
+
 /// Defines the UI entity that is the parent of this
 /// UI section.
 #[derive(Clone, Debug)]
@@ -65,7 +567,11 @@ pub struct Parent {
 
 /// Defines the children of this UI entity.
 ///
-/// Used for things like layout constraints.
+/// Used for things like layout constraints. Kept in sync with the `Parent`s of the
+/// children by the `ChildrenSystem`, which adds new children to the end.
+///
+/// The order of the children is the order layouts place them in, and later children are
+/// drawn over earlier ones.
 #[derive(Clone, Debug, Default)]
 pub struct Children {
     entities: Vec<Entity>,
@@ -79,6 +585,71 @@ impl Children {
     pub(crate) fn push(&mut self, entity: Entity) {
         self.entities.push(entity);
     }
+
+    pub(crate) fn remove(&mut self, entity: Entity) {
+        self.entities.retain(|child| *child != entity);
+    }
+
+    /// Index of `entity` in the children.
+    pub fn index_of(&self, entity: Entity) -> Option<usize> {
+        self.entities.iter().position(|child| *child == entity)
+    }
+
+    /// Moves `entity` to `index`, `false` if it isn't a child.
+    ///
+    /// This only reorders existing children. New ones come from a `Parent` pointing at this
+    /// element, see `UiBuilder::parent` and `hierarchy::set_parent`. Indices past the end put
+    /// it at the end.
+    pub fn move_to(&mut self, index: usize, entity: Entity) -> bool {
+        if self.index_of(entity).is_none() {
+            return false;
+        }
+
+        self.remove(entity);
+        let index = cmp::min(index, self.entities.len());
+        self.entities.insert(index, entity);
+        true
+    }
+
+    /// Moves `entity` to right before `sibling`, `false` if either isn't a child.
+    pub fn move_before(&mut self, entity: Entity, sibling: Entity) -> bool {
+        self.move_next_to(entity, sibling, 0)
+    }
+
+    /// Moves `entity` to right after `sibling`, `false` if either isn't a child.
+    pub fn move_after(&mut self, entity: Entity, sibling: Entity) -> bool {
+        self.move_next_to(entity, sibling, 1)
+    }
+
+    fn move_next_to(&mut self, entity: Entity, sibling: Entity, offset: usize) -> bool {
+        if entity == sibling || self.index_of(entity).is_none() || self.index_of(sibling).is_none() {
+            return false;
+        }
+
+        self.remove(entity);
+        let index = self.index_of(sibling).unwrap() + offset;
+        self.entities.insert(index, entity);
+        true
+    }
+
+    /// Swaps the places of two children, `false` if either isn't a child.
+    pub fn swap(&mut self, a: Entity, b: Entity) -> bool {
+        match (self.index_of(a), self.index_of(b)) {
+            (Some(a), Some(b)) => {
+                self.entities.swap(a, b);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Sorts the children by `key`, children with the same key keep their order.
+    pub fn sort_by_key<K, F>(&mut self, key: F)
+        where K: Ord,
+              F: FnMut(&Entity) -> K,
+    {
+        self.entities.sort_by_key(key);
+    }
 }
 
 /// Position of the UI section.
@@ -92,14 +663,57 @@ pub struct Position {
     pub x: Option<Coordinate>,
     /// Stretches to the left.
     pub y: Option<Coordinate>,
-    /// Z-ordering of UI.
+    /// Point of the parent the element is attached to, `x`/`y` are offsets from it.
+    ///
+    /// `Relative` elements are attached to their spot in the layout instead, and
+    /// `Absolute` elements to the screen.
+    pub anchor: Anchor,
+    /// Point of the element that goes on the `anchor`.
+    pub pivot: Anchor,
+    /// Z-ordering of UI, defaults to the place of the element in the hierarchy so
+    /// children are over their parents and later siblings over earlier ones.
     pub z: Option<usize>,
 }
 
+/// Point inside of an area, as a fraction of its width and height from the top-left
+/// corner.
+///
+/// Sticking an element to the bottom-right corner 16 pixels in is an anchor and pivot of
+/// `Anchor::bottom_right()` with `x`/`y` of `Pixel(-16.0)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Anchor {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Anchor {
+    pub fn new(x: f32, y: f32) -> Self {
+        Anchor {
+            x: x,
+            y: y,
+        }
+    }
+
+    pub fn top_left() -> Self { Anchor::new(0.0, 0.0) }
+    pub fn top() -> Self { Anchor::new(0.5, 0.0) }
+    pub fn top_right() -> Self { Anchor::new(1.0, 0.0) }
+    pub fn left() -> Self { Anchor::new(0.0, 0.5) }
+    pub fn center() -> Self { Anchor::new(0.5, 0.5) }
+    pub fn right() -> Self { Anchor::new(1.0, 0.5) }
+    pub fn bottom_left() -> Self { Anchor::new(0.0, 1.0) }
+    pub fn bottom() -> Self { Anchor::new(0.5, 1.0) }
+    pub fn bottom_right() -> Self { Anchor::new(1.0, 1.0) }
+}
+
 /// Type of positioning. Default is `Relative`.
 #[derive(Clone, Debug)]
 pub enum PositionKind {
     /// Positions in the parent's dimensions without regard to other portions of the UI.
+    /// `x`/`y` are offsets from the `anchor` on the parent and `Percent` coordinates are
+    /// based on the parent's width and height.
+    ///
+    /// Free elements are taken out of the flow, so siblings are laid out as if they
+    /// weren't there. Useful for overlays like badges.
     Free,
     /// Positions relative to its normal spot in the layout.
     /// `top`/`left`/`bottom`/`right` properties of the `Position` are preferred over the
@@ -118,34 +732,81 @@ pub enum PositionKind {
 
 impl Default for PositionKind {
     fn default() -> Self {
-        PositionKind::Absolute
+        PositionKind::Relative
     }
 }
 
+/// Size of the UI section.
+///
+/// `None` sizes the element to fit its content, like its `Text` or its children.
+///
+/// Whatever size the element ends up with, from its content, its `width`/`height` or a
+/// `Layout` stretching it, is kept between the minimum and maximum.
 #[derive(Clone, Debug, Default)]
 pub struct Bounds {
     pub width: Option<Coordinate>,
     pub height: Option<Coordinate>,
+    pub min_width: Option<Coordinate>,
+    pub max_width: Option<Coordinate>,
+    pub min_height: Option<Coordinate>,
+    pub max_height: Option<Coordinate>,
+    /// Width divided by the height the element has to keep.
+    ///
+    /// When only the `height` is given the width follows it, otherwise the height
+    /// follows the width. The minimum and maximum still win over the ratio.
+    pub aspect_ratio: Option<f32>,
 }
 
 /// The computed result of the `Position` and `Bounds` components.
+///
+/// `x`, `y`, `width` and `height` are the border box, the children are laid out inside
+/// of the `content_box`. Everything is in logical pixels, see `physical` for the pixels
+/// of the screen.
 #[derive(Clone, Debug, Default)]
 pub struct AbsolutePosition {
     pub x: f32,
     pub y: f32,
     pub width: f32,
     pub height: f32,
+    /// Resolved padding of the `Display`.
+    pub padding: Insets,
 
     /// Z-ordering.
     pub z: usize,
 }
 
+impl AbsolutePosition {
+    /// Area the element takes up on the screen.
+    pub fn border_box(&self) -> Rect {
+        Rect::absolute(self)
+    }
+
+    /// Area inside of the padding.
+    pub fn content_box(&self) -> Rect {
+        self.border_box().inset(&self.padding)
+    }
+
+    /// Border box in the physical pixels of `viewport`.
+    pub fn physical(&self, viewport: &Viewport) -> Rect {
+        self.border_box().to_physical(viewport.scale())
+    }
+
+    /// Border box in the physical pixels of `viewport`, snapped to whole pixels so edges
+    /// and text stay sharp on fractional scale factors like 1.25.
+    pub fn physical_snapped(&self, viewport: &Viewport) -> Rect {
+        self.physical(viewport).snap()
+    }
+}
+
 // Component quick definitions
+define_component!(Name);
 define_component!(Parent);
 define_component!(Text);
 define_component!(Layout);
+define_component!(LayoutItem);
 define_component!(Display);
 define_component!(Children);
 define_component!(Position);
 define_component!(AbsolutePosition);
 define_component!(Bounds);
+define_component!(SafeArea);
@@ -0,0 +1,417 @@
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use specs::{Entities, Entity, Join, LazyUpdate, ReadStorage, ResourceId, Resources, SystemData, World};
+
+use class::{AbsolutePosition, Bounds, Children, Coordinate, Display, Parent, Position, PositionKind};
+use class::{Text, UiScale, Units, Viewport};
+use layout::Size;
+
+/// Every entity below `entity` through their `Parent`s, without `entity` itself.
+fn descendants(world: &World, entity: Entity) -> Vec<Entity> {
+    let entities = world.entities();
+    let parents = world.read::<Parent>();
+
+    let mut tree: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (child, parent) in (&*entities, &parents).join() {
+        tree.entry(parent.entity).or_insert_with(Vec::new).push(child);
+    }
+
+    let mut found = Vec::new();
+    let mut stack = vec![entity];
+    while let Some(entity) = stack.pop() {
+        if let Some(list) = tree.remove(&entity) {
+            found.extend(list.iter().cloned());
+            stack.extend(list);
+        }
+    }
+    found
+}
+
+/// Parents `child` to `parent`, taking it out of the `Children` of its old parent.
+pub fn set_parent(world: &mut World, child: Entity, parent: Entity) {
+    let old = world.read::<Parent>().get(child).map(|old| old.entity);
+    world.write::<Parent>().insert(child, Parent { entity: parent });
+
+    let mut children = world.write::<Children>();
+    if let Some(old) = old {
+        if old != parent {
+            if let Some(list) = children.get_mut(old) {
+                list.remove(child);
+            }
+        }
+    }
+
+    if children.get(parent).is_none() {
+        children.insert(parent, Children::default());
+    }
+    let list = children.get_mut(parent).unwrap();
+    if !list.entities().contains(&child) {
+        list.push(child);
+    }
+}
+
+/// Turns `value` back into a coordinate of the same unit as `coordinate`, `Percent` being
+/// based on `parent`. Anything that isn't a `Percent` becomes `Pixel`, undoing the
+/// `UiScale`.
+fn same_unit(coordinate: Option<&Coordinate>, value: f32, parent: f32, units: &Units) -> Coordinate {
+    match coordinate {
+        Some(&Coordinate::Percent(_)) if parent != 0.0 => Coordinate::Percent(value / parent),
+        _ if units.pixel != 0.0 => Coordinate::Pixel(value / units.pixel),
+        _ => Coordinate::Pixel(value),
+    }
+}
+
+/// Font size `child` gets under `parent`, from its own `Text` or else the closest one
+/// among `parent` and its ancestors.
+fn font_size(world: &World, child: Entity, parent: Entity) -> f32 {
+    let texts = world.read::<Text>();
+    let parents = world.read::<Parent>();
+
+    if let Some(text) = texts.get(child) {
+        return text.size;
+    }
+
+    // A `Parent` loop would go on forever, so every ancestor is only looked at once.
+    let mut seen = HashSet::new();
+    let mut next = Some(parent);
+    while let Some(entity) = next {
+        if !seen.insert(entity) {
+            break;
+        }
+        if let Some(text) = texts.get(entity) {
+            return text.size;
+        }
+        next = parents.get(entity).map(|parent| parent.entity);
+    }
+    Units::default().font_size
+}
+
+/// Moves `child` under `parent` like `set_parent`, rewriting its `Position` and `Bounds`
+/// so it stays where it is on the screen.
+///
+/// `Relative` elements become `Free`, since there is no telling where the layout of the
+/// new parent would put them, and `Absolute` elements don't need to change. `Pixel` and
+/// `Percent` offsets and sizes keep their unit, anything else becomes `Pixel`. The element
+/// is always reparented, but its place is only kept when both it and its new parent have
+/// been laid out already. `Em` margins use the font size the element has under its new
+/// parent.
+pub fn reparent(world: &mut World, child: Entity, parent: Entity) {
+    let rects = {
+        let absolutes = world.read::<AbsolutePosition>();
+        match (absolutes.get(child), absolutes.get(parent)) {
+            (Some(child), Some(parent)) => Some((child.border_box(), parent.content_box())),
+            _ => None,
+        }
+    };
+    set_parent(world, child, parent);
+
+    let (rect, base) = match rects {
+        Some(rects) => rects,
+        None => return,
+    };
+
+    let units = {
+        let viewport = world.read_resource::<Viewport>();
+        let pixel = world.read_resource::<UiScale>().factor(&viewport);
+        Units {
+            viewport: Size {
+                width: viewport.logical_width(),
+                height: viewport.logical_height(),
+            },
+            font_size: font_size(world, child, parent) * pixel,
+            pixel: pixel,
+        }
+    };
+    let margin = world.read::<Display>()
+        .get(child)
+        .map(|display| display.margin.resolve(base.width, base.height, &units))
+        .unwrap_or_default();
+    let area = base.inset(&margin);
+
+    let mut positions = world.write::<Position>();
+    let position = match positions.get_mut(child) {
+        Some(position) => position,
+        None => return,
+    };
+    match position.kind {
+        PositionKind::Absolute => return,
+        PositionKind::Relative => position.kind = PositionKind::Free,
+        PositionKind::Free => { },
+    }
+
+    // Undo what the `PositionSystem` does to place a `Free` element.
+    let x = rect.x - (area.x + area.width * position.anchor.x - rect.width * position.pivot.x);
+    let y = rect.y - (area.y + area.height * position.anchor.y - rect.height * position.pivot.y);
+    position.x = Some(same_unit(position.x.as_ref(), x, base.width, &units));
+    position.y = Some(same_unit(position.y.as_ref(), y, base.height, &units));
+
+    if let Some(bounds) = world.write::<Bounds>().get_mut(child) {
+        if bounds.width.is_some() {
+            bounds.width = Some(same_unit(bounds.width.as_ref(), rect.width, base.width, &units));
+        }
+        if bounds.height.is_some() {
+            bounds.height = Some(same_unit(bounds.height.as_ref(), rect.height, base.height, &units));
+        }
+    }
+}
+
+/// Reparents `child` once the `LazyUpdate` runs, see `reparent`.
+pub fn reparent_lazy(lazy: &LazyUpdate, child: Entity, parent: Entity) {
+    lazy.execute(move |world| {
+        reparent(world, child, parent);
+    });
+}
+
+/// Deletes `entity` along with everything below it.
+///
+/// Goes by the `Parent`s rather than the `Children`, so elements parented this frame are
+/// deleted too.
+pub fn despawn_recursive(world: &mut World, entity: Entity) {
+    let below = descendants(world, entity);
+    for child in below {
+        world.delete_entity(child);
+    }
+    world.delete_entity(entity);
+}
+
+/// Deletes `entity` along with everything below it once the `LazyUpdate` runs, see
+/// `despawn_recursive`.
+pub fn despawn_recursive_lazy(lazy: &LazyUpdate, entity: Entity) {
+    lazy.execute(move |world| {
+        despawn_recursive(world, entity);
+    });
+}
+
+/// Something is wrong with the shape of the hierarchy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HierarchyError {
+    /// The `Parent`s, or the `Children`, loop back around to this entity.
+    Cycle(Entity),
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HierarchyError::Cycle(entity) => write!(f, "hierarchy loops back around to {:?}", entity),
+        }
+    }
+}
+
+impl Error for HierarchyError {
+    fn description(&self) -> &str {
+        match *self {
+            HierarchyError::Cycle(_) => "hierarchy loops back around",
+        }
+    }
+}
+
+type HierarchyData<'a> = (
+    Entities<'a>,
+    ReadStorage<'a, Parent>,
+    ReadStorage<'a, Children>,
+);
+
+/// Read-only view of the UI hierarchy, usable as `SystemData`.
+///
+/// Going up follows the `Parent`s and going down follows the `Children`, so descendants
+/// come in the order of their siblings. `Parent`s pointing at dead entities are ignored.
+///
+/// Anything that walks the hierarchy stops with a `HierarchyError` instead of going
+/// around a loop forever.
+pub struct Hierarchy<'a> {
+    entities: Entities<'a>,
+    parents: ReadStorage<'a, Parent>,
+    children: ReadStorage<'a, Children>,
+}
+
+impl<'a> SystemData<'a> for Hierarchy<'a> {
+    fn fetch(res: &'a Resources, id: usize) -> Self {
+        let (entities, parents, children) = <HierarchyData<'a> as SystemData<'a>>::fetch(res, id);
+        Hierarchy {
+            entities: entities,
+            parents: parents,
+            children: children,
+        }
+    }
+
+    fn reads(id: usize) -> Vec<ResourceId> {
+        <HierarchyData<'a> as SystemData<'a>>::reads(id)
+    }
+
+    fn writes(id: usize) -> Vec<ResourceId> {
+        <HierarchyData<'a> as SystemData<'a>>::writes(id)
+    }
+}
+
+impl<'a> Hierarchy<'a> {
+    /// Parent of `entity`, if it is alive.
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        match self.parents.get(entity) {
+            Some(parent) if self.entities.is_alive(parent.entity) => Some(parent.entity),
+            _ => None,
+        }
+    }
+
+    /// Children of `entity` in order.
+    pub fn children(&self, entity: Entity) -> &[Entity] {
+        self.children.get(entity)
+            .map(|children| &children.entities()[..])
+            .unwrap_or(&[])
+    }
+
+    /// Other children of the parent of `entity` in order, without `entity` itself.
+    pub fn siblings(&self, entity: Entity) -> Vec<Entity> {
+        match self.parent(entity) {
+            Some(parent) => {
+                self.children(parent)
+                    .iter()
+                    .cloned()
+                    .filter(|&sibling| sibling != entity)
+                    .collect()
+            },
+            None => Vec::new(),
+        }
+    }
+
+    /// Everything above `entity`, closest first.
+    pub fn ancestors<'h>(&'h self, entity: Entity) -> Ancestors<'h, 'a> {
+        let mut visited = HashSet::new();
+        visited.insert(entity);
+        Ancestors {
+            hierarchy: self,
+            current: entity,
+            visited: visited,
+            done: false,
+        }
+    }
+
+    /// Everything below `entity` depth first, each element before its children.
+    pub fn descendants<'h>(&'h self, entity: Entity) -> Descendants<'h, 'a> {
+        Descendants::new(self, entity, false)
+    }
+
+    /// Everything below `entity` breadth first, each level before the next.
+    pub fn descendants_breadth_first<'h>(&'h self, entity: Entity) -> Descendants<'h, 'a> {
+        Descendants::new(self, entity, true)
+    }
+
+    /// Element at the top of the hierarchy `entity` is in, `entity` itself if it has no
+    /// parent.
+    pub fn root(&self, entity: Entity) -> Result<Entity, HierarchyError> {
+        let mut root = entity;
+        for ancestor in self.ancestors(entity) {
+            root = ancestor?;
+        }
+        Ok(root)
+    }
+
+    /// Amount of ancestors `entity` has, `0` for a root.
+    pub fn depth(&self, entity: Entity) -> Result<usize, HierarchyError> {
+        let mut depth = 0;
+        for ancestor in self.ancestors(entity) {
+            ancestor?;
+            depth += 1;
+        }
+        Ok(depth)
+    }
+}
+
+/// Iterator over the ancestors of an element, see `Hierarchy::ancestors`.
+///
+/// Yields a single error and stops when the `Parent`s loop.
+pub struct Ancestors<'h, 'a: 'h> {
+    hierarchy: &'h Hierarchy<'a>,
+    current: Entity,
+    visited: HashSet<Entity>,
+    done: bool,
+}
+
+impl<'h, 'a> Iterator for Ancestors<'h, 'a> {
+    type Item = Result<Entity, HierarchyError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.hierarchy.parent(self.current) {
+            Some(parent) => {
+                self.current = parent;
+                if self.visited.insert(parent) {
+                    Some(Ok(parent))
+                } else {
+                    self.done = true;
+                    Some(Err(HierarchyError::Cycle(parent)))
+                }
+            },
+            None => {
+                self.done = true;
+                None
+            },
+        }
+    }
+}
+
+/// Iterator over the descendants of an element, see `Hierarchy::descendants`.
+///
+/// Yields a single error and stops when the `Children` loop.
+pub struct Descendants<'h, 'a: 'h> {
+    hierarchy: &'h Hierarchy<'a>,
+    pending: VecDeque<Entity>,
+    visited: HashSet<Entity>,
+    breadth_first: bool,
+    done: bool,
+}
+
+impl<'h, 'a> Descendants<'h, 'a> {
+    fn new(hierarchy: &'h Hierarchy<'a>, entity: Entity, breadth_first: bool) -> Self {
+        let mut descendants = Descendants {
+            hierarchy: hierarchy,
+            pending: VecDeque::new(),
+            visited: HashSet::new(),
+            breadth_first: breadth_first,
+            done: false,
+        };
+        descendants.visited.insert(entity);
+        descendants.queue(entity);
+        descendants
+    }
+
+    fn queue(&mut self, entity: Entity) {
+        let children = self.hierarchy.children(entity);
+        if self.breadth_first {
+            self.pending.extend(children.iter().cloned());
+        } else {
+            for &child in children.iter().rev() {
+                self.pending.push_front(child);
+            }
+        }
+    }
+}
+
+impl<'h, 'a> Iterator for Descendants<'h, 'a> {
+    type Item = Result<Entity, HierarchyError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.pending.pop_front() {
+            Some(entity) => {
+                if !self.visited.insert(entity) {
+                    self.done = true;
+                    return Some(Err(HierarchyError::Cycle(entity)));
+                }
+
+                self.queue(entity);
+                Some(Ok(entity))
+            },
+            None => {
+                self.done = true;
+                None
+            },
+        }
+    }
+}
@@ -0,0 +1,36 @@
+
+use class::{Flow, Units};
+use layout::{LayoutAlgorithm, LayoutChild, Rect, Size};
+
+/// Lays out a `Flow`, children go from the top of the parent to the bottom.
+///
+/// Used for elements without a `Layout`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FlowLayout;
+
+impl LayoutAlgorithm for FlowLayout {
+    type Layout = Flow;
+
+    fn measure(&self, _: &Flow, _: &Units, children: &[LayoutChild]) -> Size {
+        Size {
+            width: children.iter().map(|child| child.size.width).fold(0.0, f32::max),
+            height: children.iter().map(|child| child.size.height).sum::<f32>(),
+        }
+    }
+
+    fn arrange(&self, _: &Flow, area: &Rect, _: &Units, children: &[LayoutChild]) -> Vec<Rect> {
+        let mut flow = area.y;
+        children.iter()
+            .map(|child| {
+                let slot = Rect {
+                    x: area.x,
+                    y: flow,
+                    width: child.size.width,
+                    height: child.size.height,
+                };
+                flow += child.size.height;
+                slot
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,259 @@
+
+use std::cmp;
+use std::collections::HashSet;
+
+use class::{Grid, GridCell, Units};
+use layout::{LayoutAlgorithm, LayoutChild, Rect, Size};
+
+/// Lays out a `Grid`, see `GridCell` for how children pick their cell.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GridLayout;
+
+/// Sizes `count` tracks out of the `available` space, weighted by `stretch`.
+///
+/// Tracks without a stretch weight get `1.0`.
+fn tracks(count: usize, stretch: &[f32], available: f32) -> Vec<f32> {
+    let weights = (0..count)
+        .map(|index| stretch.get(index).cloned().unwrap_or(1.0).max(0.0))
+        .collect::<Vec<f32>>();
+    let total = weights.iter().sum::<f32>();
+
+    weights.iter()
+        .map(|weight| if total > 0.0 { available.max(0.0) * weight / total } else { 0.0 })
+        .collect()
+}
+
+/// Offset and size of `span` tracks starting at `start`, including the gaps between them.
+fn span(sizes: &[f32], gap: f32, start: usize, span: usize) -> (f32, f32) {
+    let start = cmp::min(start, sizes.len());
+    let end = cmp::min(start + cmp::max(span, 1), sizes.len());
+
+    let offset = sizes[..start].iter().sum::<f32>() + gap * start as f32;
+    let size = sizes[start..end].iter().sum::<f32>() + gap * (end - start).saturating_sub(1) as f32;
+    (offset, size)
+}
+
+/// Finds the cell of each child, along with the amount of columns and rows in the grid.
+///
+/// Children with a `GridCell` are put where it says, the rest take the next free cell
+/// going from left to right and then top to bottom.
+fn place(grid: &Grid, cells: &[Option<GridCell>]) -> (usize, usize, Vec<GridCell>) {
+    let columns = grid.columns.unwrap_or_else(|| {
+        let placed = cells.iter()
+            .filter_map(|cell| cell.as_ref())
+            .map(|cell| cell.column + cmp::max(cell.column_span, 1))
+            .max()
+            .unwrap_or(0);
+        cmp::max(grid.column_stretch.len(), placed)
+    });
+    let columns = cmp::max(columns, 1);
+
+    // Children that picked a cell claim it first.
+    let mut occupied = HashSet::new();
+    for cell in cells.iter().filter_map(|cell| cell.as_ref()) {
+        for row in cell.row..cell.row + cmp::max(cell.row_span, 1) {
+            for column in cell.column..cell.column + cmp::max(cell.column_span, 1) {
+                occupied.insert((column, row));
+            }
+        }
+    }
+
+    let mut cursor = 0;
+    let placed = cells.iter()
+        .map(|cell| match *cell {
+            Some(ref cell) => cell.clone(),
+            None => {
+                while occupied.contains(&(cursor % columns, cursor / columns)) {
+                    cursor += 1;
+                }
+
+                let cell = GridCell {
+                    column: cursor % columns,
+                    row: cursor / columns,
+                    .. GridCell::default()
+                };
+                cursor += 1;
+                cell
+            },
+        })
+        .collect::<Vec<GridCell>>();
+
+    let rows = grid.rows.unwrap_or_else(|| {
+        let needed = placed.iter()
+            .map(|cell| cell.row + cmp::max(cell.row_span, 1))
+            .max()
+            .unwrap_or(0);
+        cmp::max(grid.row_stretch.len(), needed)
+    });
+    let rows = cmp::max(rows, 1);
+
+    (columns, rows, placed)
+}
+
+/// Lays out the cells of `grid` inside of `area` and returns the cell of each child.
+fn arrange_cells(
+    grid: &Grid,
+    area: &Rect,
+    column_gap: f32,
+    row_gap: f32,
+    cells: &[Option<GridCell>],
+) -> Vec<Rect> {
+    let (columns, rows, placed) = place(grid, cells);
+
+    let widths = tracks(columns, &grid.column_stretch, area.width - column_gap * (columns - 1) as f32);
+    let heights = tracks(rows, &grid.row_stretch, area.height - row_gap * (rows - 1) as f32);
+
+    placed.iter()
+        .map(|cell| {
+            let (x, width) = span(&widths, column_gap, cell.column, cell.column_span);
+            let (y, height) = span(&heights, row_gap, cell.row, cell.row_span);
+            Rect {
+                x: area.x + x,
+                y: area.y + y,
+                width: width,
+                height: height,
+            }
+        })
+        .collect()
+}
+
+/// Grows the `tracks` from `start` to fit `size`, split evenly over the `span`.
+fn fit(tracks: &mut [f32], start: usize, span: usize, size: f32) {
+    let end = cmp::min(start + cmp::max(span, 1), tracks.len());
+    if start >= end {
+        return;
+    }
+
+    let share = size / (end - start) as f32;
+    for track in &mut tracks[start..end] {
+        *track = track.max(share);
+    }
+}
+
+fn cells(children: &[LayoutChild]) -> Vec<Option<GridCell>> {
+    children.iter()
+        .map(|child| child.item::<GridCell>().cloned())
+        .collect()
+}
+
+impl LayoutAlgorithm for GridLayout {
+    type Layout = Grid;
+
+    /// Every column is as wide as its widest child and every row as tall as its tallest.
+    fn measure(&self, grid: &Grid, units: &Units, children: &[LayoutChild]) -> Size {
+        let (columns, rows, placed) = place(grid, &cells(children));
+
+        let mut widths = vec![0.0; columns];
+        let mut heights = vec![0.0; rows];
+        for (cell, child) in placed.iter().zip(children) {
+            fit(&mut widths, cell.column, cell.column_span, child.size.width);
+            fit(&mut heights, cell.row, cell.row_span, child.size.height);
+        }
+
+        // There is nothing to take a percentage of yet.
+        let column_gap = grid.column_gap.resolve(0.0, units);
+        let row_gap = grid.row_gap.resolve(0.0, units);
+        Size {
+            width: widths.iter().sum::<f32>() + column_gap * (columns - 1) as f32,
+            height: heights.iter().sum::<f32>() + row_gap * (rows - 1) as f32,
+        }
+    }
+
+    /// Children without a `width`/`height` in their `Bounds` fill their cell.
+    fn arrange(&self, grid: &Grid, area: &Rect, units: &Units, children: &[LayoutChild]) -> Vec<Rect> {
+        let column_gap = grid.column_gap.resolve(area.width, units);
+        let row_gap = grid.row_gap.resolve(area.height, units);
+
+        arrange_cells(grid, area, column_gap, row_gap, &cells(children))
+            .iter()
+            .zip(children)
+            .map(|(cell, child)| {
+                let size = child.clamp(Size {
+                    width: if child.fixed_width { child.size.width } else { cell.width },
+                    height: if child.fixed_height { child.size.height } else { cell.height },
+                });
+                Rect {
+                    x: cell.x,
+                    y: cell.y,
+                    width: size.width,
+                    height: size.height,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_split_by_stretch() {
+        assert_eq!(tracks(3, &[2.0], 400.0), vec![200.0, 100.0, 100.0]);
+        assert_eq!(tracks(2, &[0.0, 0.0], 100.0), vec![0.0, 0.0]);
+        assert_eq!(tracks(2, &[], -50.0), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn span_includes_gaps() {
+        let sizes = [100.0, 50.0, 25.0];
+        assert_eq!(span(&sizes, 10.0, 0, 1), (0.0, 100.0));
+        assert_eq!(span(&sizes, 10.0, 1, 2), (110.0, 85.0));
+        // Spans past the end are cut off at the last track.
+        assert_eq!(span(&sizes, 10.0, 2, 5), (170.0, 25.0));
+        assert_eq!(span(&sizes, 10.0, 1, 0), (110.0, 50.0));
+    }
+
+    #[test]
+    fn place_fills_free_cells() {
+        let grid = Grid {
+            columns: Some(2),
+            .. Grid::default()
+        };
+        let picked = GridCell {
+            column: 0,
+            row: 0,
+            .. GridCell::default()
+        };
+        let (columns, rows, placed) = place(&grid, &[None, Some(picked), None, None]);
+
+        assert_eq!((columns, rows), (2, 2));
+        let spots = placed.iter().map(|cell| (cell.column, cell.row)).collect::<Vec<_>>();
+        assert_eq!(spots, vec![(1, 0), (0, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn place_sizes_to_cells() {
+        let wide = GridCell {
+            column: 1,
+            row: 2,
+            column_span: 2,
+            .. GridCell::default()
+        };
+        let (columns, rows, _) = place(&Grid::default(), &[Some(wide)]);
+        assert_eq!((columns, rows), (3, 3));
+
+        let (columns, rows, placed) = place(&Grid::default(), &[]);
+        assert_eq!((columns, rows, placed.len()), (1, 1, 0));
+    }
+
+    #[test]
+    fn cells_cover_the_area() {
+        let grid = Grid {
+            columns: Some(2),
+            rows: Some(1),
+            .. Grid::default()
+        };
+        let area = Rect {
+            x: 10.0,
+            y: 20.0,
+            width: 210.0,
+            height: 50.0,
+        };
+        let rects = arrange_cells(&grid, &area, 10.0, 0.0, &[None, None]);
+        assert_eq!(rects, vec![
+            Rect { x: 10.0, y: 20.0, width: 100.0, height: 50.0 },
+            Rect { x: 120.0, y: 20.0, width: 100.0, height: 50.0 },
+        ]);
+    }
+}
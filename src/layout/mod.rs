@@ -0,0 +1,341 @@
+
+pub mod flow;
+pub mod grid;
+pub mod stack;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::f32;
+
+use specs::Entity;
+
+use class::{AbsolutePosition, Layout, LayoutItem, Units, Viewport};
+
+pub use self::flow::FlowLayout;
+pub use self::grid::GridLayout;
+pub use self::stack::StackLayout;
+
+/// Area that positions and bounds get resolved against.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// The whole screen, in logical pixels.
+    pub fn viewport(viewport: &Viewport) -> Self {
+        Rect {
+            x: 0.0,
+            y: 0.0,
+            width: viewport.logical_width(),
+            height: viewport.logical_height(),
+        }
+    }
+
+    /// The part of the screen that isn't cut off, in logical pixels.
+    pub fn safe_area(viewport: &Viewport) -> Self {
+        let scale = viewport.scale();
+        let safe_area = viewport.safe_area;
+        Rect::viewport(viewport).inset(&Insets {
+            top: safe_area.top / scale,
+            bottom: safe_area.bottom / scale,
+            left: safe_area.left / scale,
+            right: safe_area.right / scale,
+        })
+    }
+
+    /// The area an element was last computed to take up.
+    pub fn absolute(absolute: &AbsolutePosition) -> Self {
+        Rect {
+            x: absolute.x,
+            y: absolute.y,
+            width: absolute.width,
+            height: absolute.height,
+        }
+    }
+
+    /// Shrinks the area by `insets`, never below nothing.
+    pub fn inset(&self, insets: &Insets) -> Self {
+        Rect {
+            x: self.x + insets.left,
+            y: self.y + insets.top,
+            width: (self.width - insets.horizontal()).max(0.0),
+            height: (self.height - insets.vertical()).max(0.0),
+        }
+    }
+
+    /// Turns logical pixels into physical ones.
+    pub fn to_physical(&self, scale_factor: f32) -> Self {
+        Rect {
+            x: self.x * scale_factor,
+            y: self.y * scale_factor,
+            width: self.width * scale_factor,
+            height: self.height * scale_factor,
+        }
+    }
+
+    /// Rounds the edges to whole pixels.
+    ///
+    /// The edges are rounded instead of the size, so elements that touch keep touching.
+    pub fn snap(&self) -> Self {
+        let left = self.x.round();
+        let top = self.y.round();
+        Rect {
+            x: left,
+            y: top,
+            width: (self.x + self.width).round() - left,
+            height: (self.y + self.height).round() - top,
+        }
+    }
+}
+
+/// Resolved length on each side of an element.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Insets {
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
+/// Width and height of an element.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Smallest and largest an element can be, along with the ratio of its width to its
+/// height it has to keep.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits {
+    pub min: Size,
+    pub max: Size,
+    pub aspect_ratio: Option<f32>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            min: Size::default(),
+            max: Size {
+                width: f32::INFINITY,
+                height: f32::INFINITY,
+            },
+            aspect_ratio: None,
+        }
+    }
+}
+
+impl Limits {
+    fn clamp_width(&self, width: f32) -> f32 {
+        width.min(self.max.width).max(self.min.width)
+    }
+
+    fn clamp_height(&self, height: f32) -> f32 {
+        height.min(self.max.height).max(self.min.height)
+    }
+
+    /// Fits `size` into the limits.
+    ///
+    /// With an aspect ratio one side follows the other, the width leads unless
+    /// `height_leads`. The minimum and maximum win over the aspect ratio when both can't
+    /// be kept.
+    pub fn apply(&self, size: Size, height_leads: bool) -> Size {
+        let mut size = Size {
+            width: self.clamp_width(size.width),
+            height: self.clamp_height(size.height),
+        };
+
+        match self.aspect_ratio {
+            Some(ratio) if ratio > 0.0 => {
+                if height_leads {
+                    size.width = self.clamp_width(size.height * ratio);
+                    size.height = self.clamp_height(size.width / ratio);
+                } else {
+                    size.height = self.clamp_height(size.width / ratio);
+                    size.width = self.clamp_width(size.height * ratio);
+                }
+            },
+            _ => { },
+        }
+
+        size
+    }
+}
+
+/// A child of the element being laid out.
+#[derive(Clone, Debug)]
+pub struct LayoutChild<'l> {
+    pub entity: Entity,
+    /// Size the child would like to be.
+    pub size: Size,
+    /// Whether the width came from the child's `Bounds`, children without one can be
+    /// stretched by the layout.
+    pub fixed_width: bool,
+    /// Whether the height came from the child's `Bounds`, children without one can be
+    /// stretched by the layout.
+    pub fixed_height: bool,
+    /// How big or small the child is allowed to be, layouts should `clamp` whatever size
+    /// they give it.
+    pub limits: Limits,
+    /// Settings the child has for its parent's layout.
+    pub item: Option<&'l LayoutItem>,
+}
+
+impl<'l> LayoutChild<'l> {
+    /// Fits `size` into the `limits` of the child.
+    pub fn clamp(&self, size: Size) -> Size {
+        self.limits.apply(size, self.fixed_height && !self.fixed_width)
+    }
+
+    /// Gets the settings of the child if they are a `T`.
+    pub fn item<T: Any>(&self) -> Option<&'l T> {
+        self.item.and_then(|item| item.get::<T>())
+    }
+}
+
+/// Places the children of elements that have a `Layout` holding a `Self::Layout`.
+///
+/// Only `Relative` children are handed to the layout, the rest are taken out of it.
+/// Register implementations in the `Layouts` resource. Coordinates in the layout should
+/// be resolved with the `units` of the element.
+pub trait LayoutAlgorithm: Send + Sync + 'static {
+    /// Type that goes in the `Layout` of the parent.
+    type Layout: Any + Send + Sync;
+
+    /// How big the element would like to be to fit all of its `children`.
+    fn measure(&self, layout: &Self::Layout, units: &Units, children: &[LayoutChild]) -> Size;
+
+    /// Places the `children` inside of `area`, returning the area of each child in the
+    /// same order.
+    fn arrange(&self, layout: &Self::Layout, area: &Rect, units: &Units, children: &[LayoutChild]) -> Vec<Rect>;
+}
+
+/// `LayoutAlgorithm` without the type of its layout.
+trait AnyLayoutAlgorithm: Send + Sync {
+    fn measure(&self, layout: &Layout, units: &Units, children: &[LayoutChild]) -> Option<Size>;
+    fn arrange(&self, layout: &Layout, area: &Rect, units: &Units, children: &[LayoutChild]) -> Option<Vec<Rect>>;
+}
+
+impl<A: LayoutAlgorithm> AnyLayoutAlgorithm for A {
+    fn measure(&self, layout: &Layout, units: &Units, children: &[LayoutChild]) -> Option<Size> {
+        layout.get::<A::Layout>()
+            .map(|layout| LayoutAlgorithm::measure(self, layout, units, children))
+    }
+    fn arrange(&self, layout: &Layout, area: &Rect, units: &Units, children: &[LayoutChild]) -> Option<Vec<Rect>> {
+        layout.get::<A::Layout>()
+            .map(|layout| LayoutAlgorithm::arrange(self, layout, area, units, children))
+    }
+}
+
+/// Resource that maps the types that go in a `Layout` to the algorithm laying them out.
+///
+/// The default has the `Grid` and `Stack` layouts registered.
+pub struct Layouts {
+    algorithms: HashMap<TypeId, Box<AnyLayoutAlgorithm>>,
+}
+
+impl Default for Layouts {
+    fn default() -> Self {
+        let mut layouts = Layouts::new();
+        layouts.register(GridLayout);
+        layouts.register(StackLayout);
+        layouts
+    }
+}
+
+impl Layouts {
+    /// Registry without any layouts in it.
+    pub fn new() -> Self {
+        Layouts {
+            algorithms: HashMap::new(),
+        }
+    }
+
+    /// Lays out elements with a `A::Layout` using `algorithm`, replacing whatever laid
+    /// them out before.
+    pub fn register<A: LayoutAlgorithm>(&mut self, algorithm: A) {
+        self.algorithms.insert(TypeId::of::<A::Layout>(), Box::new(algorithm));
+    }
+
+    /// Whether there is an algorithm for the `layout`.
+    pub fn contains(&self, layout: &Layout) -> bool {
+        self.algorithms.contains_key(&layout.kind())
+    }
+
+    /// Measures with the algorithm registered for the `layout`, `None` if there is none.
+    pub fn measure(&self, layout: &Layout, units: &Units, children: &[LayoutChild]) -> Option<Size> {
+        self.algorithms.get(&layout.kind())
+            .and_then(|algorithm| algorithm.measure(layout, units, children))
+    }
+
+    /// Arranges with the algorithm registered for the `layout`, `None` if there is none.
+    pub fn arrange(&self, layout: &Layout, area: &Rect, units: &Units, children: &[LayoutChild]) -> Option<Vec<Rect>> {
+        self.algorithms.get(&layout.kind())
+            .and_then(|algorithm| algorithm.arrange(layout, area, units, children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: f32, height: f32) -> Size {
+        Size {
+            width: width,
+            height: height,
+        }
+    }
+
+    #[test]
+    fn limits_clamp() {
+        let limits = Limits {
+            min: size(10.0, 10.0),
+            max: size(100.0, 50.0),
+            aspect_ratio: None,
+        };
+        assert_eq!(limits.apply(size(200.0, 5.0), false), size(100.0, 10.0));
+        assert_eq!(limits.apply(size(20.0, 20.0), false), size(20.0, 20.0));
+        assert_eq!(Limits::default().apply(size(1e6, 0.0), false), size(1e6, 0.0));
+    }
+
+    #[test]
+    fn aspect_ratio_follows_the_leading_side() {
+        let limits = Limits {
+            aspect_ratio: Some(2.0),
+            .. Limits::default()
+        };
+        assert_eq!(limits.apply(size(100.0, 100.0), false), size(100.0, 50.0));
+        assert_eq!(limits.apply(size(100.0, 30.0), true), size(60.0, 30.0));
+    }
+
+    #[test]
+    fn limits_win_over_aspect_ratio() {
+        let limits = Limits {
+            min: size(100.0, 0.0),
+            max: size(f32::INFINITY, 20.0),
+            aspect_ratio: Some(1.0),
+        };
+        assert_eq!(limits.apply(size(50.0, 50.0), false), size(100.0, 20.0));
+
+        let limits = Limits {
+            aspect_ratio: Some(0.0),
+            .. Limits::default()
+        };
+        assert_eq!(limits.apply(size(30.0, 40.0), false), size(30.0, 40.0));
+    }
+}
@@ -0,0 +1,275 @@
+
+use class::{Align, Direction, Flex, Justify, Stack, Units};
+use layout::{LayoutAlgorithm, LayoutChild, Rect, Size};
+
+/// Lays out a `Stack`, see `Flex` for how children grow and shrink.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StackLayout;
+
+/// A child of a `Stack` going into `arrange_items`.
+#[derive(Clone, Debug)]
+struct StackItem {
+    /// Size along the direction of the stack.
+    main: f32,
+    /// Size across the direction of the stack, `None` fills the stack.
+    cross: Option<f32>,
+    /// Share of the free space this item grows by.
+    grow: f32,
+    /// Share of the missing space this item shrinks by, weighted by its `main` size.
+    shrink: f32,
+    /// Smallest and largest the item can be along the direction of the stack.
+    main_limits: (f32, f32),
+    /// Smallest and largest the item can be across the direction of the stack.
+    cross_limits: (f32, f32),
+}
+
+/// Keeps `size` between the `(min, max)` of `limits`.
+fn clamp(size: f32, limits: (f32, f32)) -> f32 {
+    size.min(limits.1).max(limits.0)
+}
+
+/// Lays out `items` one after another inside of `area` and returns the area of each item.
+fn arrange_items(stack: &Stack, area: &Rect, gap: f32, items: &[StackItem]) -> Vec<Rect> {
+    if items.is_empty() {
+        return Vec::new();
+    }
+
+    let (main_start, main_size, cross_start, cross_size) = match stack.direction {
+        Direction::Row => (area.x, area.width, area.y, area.height),
+        Direction::Column => (area.y, area.height, area.x, area.width),
+    };
+
+    let gaps = gap * (items.len() - 1) as f32;
+    let used = items.iter().map(|item| item.main).sum::<f32>();
+    let free = main_size - used - gaps;
+
+    let mut sizes = items.iter().map(|item| item.main).collect::<Vec<f32>>();
+    let grow = items.iter().map(|item| item.grow.max(0.0)).sum::<f32>();
+    let shrink = items.iter().map(|item| item.shrink.max(0.0) * item.main).sum::<f32>();
+
+    // Grow into the free space or shrink out of the missing space.
+    let mut remaining = free;
+    if free > 0.0 && grow > 0.0 {
+        for (size, item) in sizes.iter_mut().zip(items) {
+            *size += free * item.grow.max(0.0) / grow;
+        }
+        remaining = 0.0;
+    } else if free < 0.0 && shrink > 0.0 {
+        for (size, item) in sizes.iter_mut().zip(items) {
+            *size = (*size + free * item.shrink.max(0.0) * item.main / shrink).max(0.0);
+        }
+        remaining = 0.0;
+    }
+
+    // Items that hit their limits keep them, whatever they gave up or took is left over.
+    for (size, item) in sizes.iter_mut().zip(items) {
+        let clamped = clamp(*size, item.main_limits);
+        remaining += *size - clamped;
+        *size = clamped;
+    }
+
+    // Whatever space is left over is handed out by the justification.
+    let remaining = remaining.max(0.0);
+    let count = items.len() as f32;
+    let (mut cursor, spacing) = match stack.justify {
+        Justify::Start => (0.0, 0.0),
+        Justify::End => (remaining, 0.0),
+        Justify::Center => (remaining / 2.0, 0.0),
+        Justify::SpaceBetween if items.len() > 1 => (0.0, remaining / (count - 1.0)),
+        Justify::SpaceBetween => (0.0, 0.0),
+        Justify::SpaceAround => (remaining / count / 2.0, remaining / count),
+        Justify::SpaceEvenly => (remaining / (count + 1.0), remaining / (count + 1.0)),
+    };
+
+    let mut rects = Vec::with_capacity(items.len());
+    for (size, item) in sizes.into_iter().zip(items) {
+        let cross = match item.cross {
+            Some(cross) => cross,
+            None => clamp(cross_size, item.cross_limits),
+        };
+        let cross_offset = match stack.align {
+            Align::Start | Align::Stretch => 0.0,
+            Align::End => cross_size - cross,
+            Align::Center => (cross_size - cross) / 2.0,
+        };
+
+        let main = main_start + cursor;
+        let across = cross_start + cross_offset;
+        rects.push(match stack.direction {
+            Direction::Row => Rect {
+                x: main,
+                y: across,
+                width: size,
+                height: cross,
+            },
+            Direction::Column => Rect {
+                x: across,
+                y: main,
+                width: cross,
+                height: size,
+            },
+        });
+
+        cursor += size + gap + spacing;
+    }
+
+    rects
+}
+
+/// Splits `size` into its length along and across the direction of the stack.
+fn axes(direction: Direction, size: &Size) -> (f32, f32) {
+    match direction {
+        Direction::Row => (size.width, size.height),
+        Direction::Column => (size.height, size.width),
+    }
+}
+
+impl LayoutAlgorithm for StackLayout {
+    type Layout = Stack;
+
+    /// Long enough for every child one after another and as wide as the widest child.
+    fn measure(&self, stack: &Stack, units: &Units, children: &[LayoutChild]) -> Size {
+        // There is nothing to take a percentage of yet.
+        let gap = stack.gap.resolve(0.0, units);
+        let gaps = gap * children.len().saturating_sub(1) as f32;
+
+        let main = children.iter().map(|child| axes(stack.direction, &child.size).0).sum::<f32>() + gaps;
+        let cross = children.iter().map(|child| axes(stack.direction, &child.size).1).fold(0.0, f32::max);
+        match stack.direction {
+            Direction::Row => Size { width: main, height: cross },
+            Direction::Column => Size { width: cross, height: main },
+        }
+    }
+
+    fn arrange(&self, stack: &Stack, area: &Rect, units: &Units, children: &[LayoutChild]) -> Vec<Rect> {
+        let (main, cross) = axes(stack.direction, &Size { width: area.width, height: area.height });
+        let gap = stack.gap.resolve(main, units);
+
+        let items = children.iter()
+            .map(|child| {
+                let (size, across) = axes(stack.direction, &child.size);
+                let fixed_across = match stack.direction {
+                    Direction::Row => child.fixed_height,
+                    Direction::Column => child.fixed_width,
+                };
+                let flex = child.item::<Flex>().cloned().unwrap_or_default();
+                let (min, min_across) = axes(stack.direction, &child.limits.min);
+                let (max, max_across) = axes(stack.direction, &child.limits.max);
+
+                StackItem {
+                    main: size,
+                    cross: match stack.align {
+                        Align::Stretch if !fixed_across => None,
+                        _ => Some(across.min(cross)),
+                    },
+                    grow: flex.grow,
+                    shrink: flex.shrink,
+                    main_limits: (min, max),
+                    cross_limits: (min_across, max_across),
+                }
+            })
+            .collect::<Vec<StackItem>>();
+
+        arrange_items(stack, area, gap, &items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32;
+
+    use super::*;
+
+    fn item(main: f32, grow: f32, shrink: f32) -> StackItem {
+        StackItem {
+            main: main,
+            cross: None,
+            grow: grow,
+            shrink: shrink,
+            main_limits: (0.0, f32::INFINITY),
+            cross_limits: (0.0, f32::INFINITY),
+        }
+    }
+
+    fn area(width: f32, height: f32) -> Rect {
+        Rect {
+            x: 0.0,
+            y: 0.0,
+            width: width,
+            height: height,
+        }
+    }
+
+    fn row() -> Stack {
+        Stack {
+            direction: Direction::Row,
+            .. Stack::default()
+        }
+    }
+
+    #[test]
+    fn grow_splits_free_space() {
+        let rects = arrange_items(&row(), &area(300.0, 50.0), 0.0, &[item(50.0, 1.0, 0.0), item(50.0, 3.0, 0.0)]);
+        assert_eq!(rects, vec![
+            Rect { x: 0.0, y: 0.0, width: 100.0, height: 50.0 },
+            Rect { x: 100.0, y: 0.0, width: 200.0, height: 50.0 },
+        ]);
+    }
+
+    #[test]
+    fn shrink_is_weighted_by_size() {
+        let rects = arrange_items(&row(), &area(150.0, 50.0), 0.0, &[item(200.0, 0.0, 1.0), item(100.0, 0.0, 1.0)]);
+        assert_eq!(rects[0].width, 100.0);
+        assert_eq!(rects[1].width, 50.0);
+        assert_eq!(rects[1].x, 100.0);
+    }
+
+    #[test]
+    fn limits_leave_space_to_justify() {
+        let stack = Stack {
+            justify: Justify::End,
+            .. row()
+        };
+        let mut limited = item(50.0, 1.0, 0.0);
+        limited.main_limits = (0.0, 60.0);
+
+        let rects = arrange_items(&stack, &area(300.0, 50.0), 0.0, &[limited, item(50.0, 1.0, 0.0)]);
+        assert_eq!((rects[0].x, rects[0].width), (90.0, 60.0));
+        assert_eq!((rects[1].x, rects[1].width), (150.0, 150.0));
+    }
+
+    #[test]
+    fn space_between_and_gaps() {
+        let stack = Stack {
+            direction: Direction::Column,
+            justify: Justify::SpaceBetween,
+            .. Stack::default()
+        };
+        let items = [item(50.0, 0.0, 0.0), item(50.0, 0.0, 0.0), item(50.0, 0.0, 0.0)];
+        let rects = arrange_items(&stack, &area(40.0, 300.0), 10.0, &items);
+        let offsets = rects.iter().map(|rect| rect.y).collect::<Vec<f32>>();
+        assert_eq!(offsets, vec![0.0, 125.0, 250.0]);
+        assert!(rects.iter().all(|rect| rect.width == 40.0));
+    }
+
+    #[test]
+    fn align_across() {
+        let stack = Stack {
+            align: Align::Center,
+            .. row()
+        };
+        let mut fixed = item(50.0, 0.0, 0.0);
+        fixed.cross = Some(20.0);
+        let mut stretched = item(50.0, 0.0, 0.0);
+        stretched.cross_limits = (0.0, 30.0);
+
+        let rects = arrange_items(&stack, &area(100.0, 50.0), 0.0, &[fixed, stretched]);
+        assert_eq!((rects[0].y, rects[0].height), (15.0, 20.0));
+        assert_eq!((rects[1].y, rects[1].height), (10.0, 30.0));
+    }
+
+    #[test]
+    fn nothing_to_arrange() {
+        assert!(arrange_items(&row(), &area(100.0, 100.0), 10.0, &[]).is_empty());
+    }
+}
@@ -1,21 +1,87 @@
 
 extern crate specs;
 extern crate hibitset;
+#[cfg(feature = "solver")]
+extern crate cassowary;
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
 
-use specs::{Dispatcher, DispatcherBuilder};
+use specs::{Dispatcher, DispatcherBuilder, World};
 
 pub mod systems;
 pub mod class;
 pub mod ui;
-mod track;
+pub mod layout;
+pub mod hierarchy;
+pub mod name;
+#[cfg(feature = "solver")]
+pub mod solver;
+pub mod track;
 
+/// Options for the systems `dispatcher_with` builds.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Deletes elements whose parent died, see `ChildrenSystem::despawn_orphans`.
+    pub despawn_orphans: bool,
+}
+
+/// Registers all of the UI components and adds the default UI resources to `world`.
+///
+/// The `class::Viewport` is left out since only the window knows how big it is, it has to
+/// be added separately before dispatching. The `class::UiScale` keeps pixels as they are
+/// until a different one is added over it.
+pub fn register(world: &mut World) {
+    world.register::<class::Name>();
+    world.register::<class::Parent>();
+    world.register::<class::Children>();
+    world.register::<class::Text>();
+    world.register::<class::Layout>();
+    world.register::<class::LayoutItem>();
+    world.register::<class::Display>();
+    world.register::<class::Position>();
+    world.register::<class::AbsolutePosition>();
+    world.register::<class::Bounds>();
+    world.register::<class::SafeArea>();
+
+    world.add_resource(class::UiScale::default());
+    world.add_resource(layout::Layouts::default());
+    world.add_resource(name::NameIndex::default());
+
+    #[cfg(feature = "solver")]
+    {
+        world.register::<solver::Constraints>();
+        world.add_resource(solver::Changes::default());
+    }
+}
+
+/// Builds a dispatcher with all of the UI systems, using the default `Config`.
+///
+/// The world has to be set up with `register` and have a `class::Viewport`. Change flags are cleared at the end by `systems::reset::ResetSystem`.
 pub fn dispatcher<'a, 'b>() -> Dispatcher<'a, 'b> {
-    let mut dispatcher = DispatcherBuilder::new()
-        //.add(systems::changed::ChangedSystem, "changed", &[])
-        //.add(systems::children::ChildrenSystem, "children", &[])
-        .add(systems::position::PositionSystem, "citrine/position", &[])
-        .build();
+    dispatcher_with(Config::default())
+}
+
+/// Builds a dispatcher with all of the UI systems, set up by `config`, see `dispatcher`.
+pub fn dispatcher_with<'a, 'b>(config: Config) -> Dispatcher<'a, 'b> {
+    let children = systems::children::ChildrenSystem::new()
+        .despawn_orphans(config.despawn_orphans);
+
+    let builder = DispatcherBuilder::new()
+        .add(name::NameSystem, "citrine/name", &[])
+        .add(children, "citrine/children", &[])
+        .add(systems::position::PositionSystem::new(), "citrine/position", &["citrine/children"]);
+
+    #[cfg(feature = "solver")]
+    let builder = builder
+        .add(solver::SolverSystem::new(), "citrine/solver", &["citrine/position"])
+        .add(systems::changed::ChangedSystem, "citrine/changed", &["citrine/solver"]);
+
+    let builder = builder
+        .add(systems::reset::ResetSystem, "citrine/reset", &["citrine/name", "citrine/position"]);
 
-    dispatcher
+    builder.build()
 }
 
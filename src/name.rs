@@ -0,0 +1,175 @@
+
+use std::collections::HashMap;
+
+use hibitset::BitSetLike;
+use specs::{Entities, Entity, FetchMut, Index, Join, ReadStorage, System};
+
+use class::{Name, Parent};
+
+/// Resource for finding elements by their `Name`, kept up to date by the `NameSystem`.
+#[derive(Clone, Debug, Default)]
+pub struct NameIndex {
+    /// Elements with each name, by id.
+    named: HashMap<String, Vec<Entity>>,
+    /// Name of every element, by the id that flags point at.
+    names: HashMap<Index, (Entity, String)>,
+    /// Parent of every element with a `Parent`, by id.
+    parents: HashMap<Index, (Entity, Entity)>,
+}
+
+impl NameIndex {
+    pub fn new() -> Self {
+        NameIndex::default()
+    }
+
+    /// Every element with `name`, by id.
+    pub fn all(&self, name: &str) -> &[Entity] {
+        self.named.get(name).map(|list| &list[..]).unwrap_or(&[])
+    }
+
+    /// First element with `name`, see `all`.
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.all(name).first().cloned()
+    }
+
+    /// Name of `entity`.
+    pub fn name(&self, entity: Entity) -> Option<&str> {
+        match self.names.get(&entity.id()) {
+            Some(&(named, ref name)) if named == entity => Some(&name[..]),
+            _ => None,
+        }
+    }
+
+    fn parent(&self, entity: Entity) -> Option<Entity> {
+        match self.parents.get(&entity.id()) {
+            Some(&(child, parent)) if child == entity => Some(parent),
+            _ => None,
+        }
+    }
+
+    /// Closest element above `entity` that has a name.
+    fn named_ancestor(&self, entity: Entity) -> Option<Entity> {
+        let mut current = entity;
+        // Walking more steps than there are parents means the hierarchy loops.
+        for _ in 0..self.parents.len() {
+            current = match self.parent(current) {
+                Some(parent) => parent,
+                None => return None,
+            };
+            if self.name(current).is_some() {
+                return Some(current);
+            }
+        }
+        None
+    }
+
+    /// Finds an element by the names leading down to it, like `"main_menu/options/volume"`.
+    ///
+    /// Each name is the closest named element above the next one, so elements without a
+    /// name in between are skipped, and the first name is an element without a named
+    /// ancestor. When more than one element matches, the one found first by id wins.
+    pub fn find(&self, path: &str) -> Option<Entity> {
+        let segments = path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<&str>>();
+        let (last, rest) = match segments.split_last() {
+            Some(split) => split,
+            None => return None,
+        };
+
+        self.all(last)
+            .iter()
+            .cloned()
+            .find(|&candidate| {
+                let mut current = candidate;
+                for segment in rest.iter().rev() {
+                    match self.named_ancestor(current) {
+                        Some(ancestor) if self.name(ancestor) == Some(*segment) => current = ancestor,
+                        _ => return false,
+                    }
+                }
+                self.named_ancestor(current).is_none()
+            })
+    }
+
+    fn insert_name(&mut self, entity: Entity, name: &str) {
+        if self.name(entity) == Some(name) {
+            return;
+        }
+        // Also takes out a dead element that had the same id.
+        self.remove_name(entity.id());
+
+        let list = self.named.entry(name.to_owned()).or_insert_with(Vec::new);
+        let index = match list.binary_search_by_key(&entity.id(), |entity| entity.id()) {
+            Ok(index) | Err(index) => index,
+        };
+        list.insert(index, entity);
+        self.names.insert(entity.id(), (entity, name.to_owned()));
+    }
+
+    fn remove_name(&mut self, id: Index) {
+        if let Some((entity, name)) = self.names.remove(&id) {
+            let empty = match self.named.get_mut(&name) {
+                Some(list) => {
+                    list.retain(|named| *named != entity);
+                    list.is_empty()
+                },
+                None => false,
+            };
+            if empty {
+                self.named.remove(&name);
+            }
+        }
+    }
+}
+
+/// Keeps the `NameIndex` up to date.
+///
+/// New, changed and removed `Name`s and `Parent`s are picked up through their flags, so
+/// it has to run before the `ResetSystem`. Deleted elements lose their components once
+/// the world is maintained, which flags them as removed.
+pub struct NameSystem;
+impl<'a> System<'a> for NameSystem {
+    type SystemData = (
+        Entities<'a>,
+        FetchMut<'a, NameIndex>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Parent>,
+    );
+    fn run(&mut self, (entities, mut index, names, parents): Self::SystemData) {
+        {
+            let flagged = names.open().1;
+            for (entity, name) in (&*entities, flagged).join() {
+                index.insert_name(entity, &name.0);
+            }
+        }
+        {
+            let flagged = parents.open().1;
+            for (entity, parent) in (&*entities, flagged).join() {
+                index.parents.insert(entity.id(), (entity, parent.entity));
+            }
+        }
+
+        // A removed flag only has the id, whatever is indexed there might have been put
+        // back or belong to a new element by now.
+        for id in names.open().1.removed().iter() {
+            let stale = match index.names.get(&id) {
+                Some(&(entity, _)) => !entities.is_alive(entity) || names.get(entity).is_none(),
+                None => false,
+            };
+            if stale {
+                index.remove_name(id);
+            }
+        }
+
+        for id in parents.open().1.removed().iter() {
+            let stale = match index.parents.get(&id) {
+                Some(&(entity, _)) => !entities.is_alive(entity) || parents.get(entity).is_none(),
+                None => false,
+            };
+            if stale {
+                index.parents.remove(&id);
+            }
+        }
+    }
+}
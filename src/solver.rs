@@ -0,0 +1,555 @@
+
+use std::collections::{HashMap, HashSet};
+use std::ops::{Add, Mul, Neg, Sub};
+
+use cassowary::{self, AddConstraintError, RelationalOperator, Solver, Term, Variable};
+use cassowary::strength::{REQUIRED, WEAK};
+use specs::{Component, DenseVecStorage, Entities, Entity, FetchMut, Fetch, Join, ReadStorage, System, WriteStorage};
+
+use ::class::{AbsolutePosition, Viewport};
+use ::track::TrackStorage;
+
+pub use cassowary::strength;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum KeyId {
+    Entity(Entity),
+    Context,
+    Unkeyed,
+}
+
+impl Default for KeyId {
+    fn default() -> KeyId {
+        KeyId::Unkeyed
+    }
+}
+
+/// Key for picking out variables used in the solver.
+///
+/// Mainly just useful or attaching some meaning to them.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Key(pub KeyId, pub &'static str);
+
+/// Variables the `SolverSystem` changed during the last frame, along with the constraints
+/// it couldn't add.
+#[derive(Clone, Default, Debug)]
+pub struct Changes {
+    changes: Vec<(Key, Variable, f64)>,
+    errors: Vec<(cassowary::Constraint, AddConstraintError)>,
+}
+
+impl Changes {
+    pub fn set_changes(&mut self, changes: Vec<(Key, Variable, f64)>) {
+        self.changes = changes;
+    }
+
+    pub fn changes(&self) -> &Vec<(Key, Variable, f64)> {
+        &self.changes
+    }
+
+    pub fn set_errors(&mut self, errors: Vec<(cassowary::Constraint, AddConstraintError)>) {
+        self.errors = errors;
+    }
+
+    /// Constraints that were left out during the last frame, like ones that can't be
+    /// satisfied along with the required ones already in the solver.
+    pub fn errors(&self) -> &Vec<(cassowary::Constraint, AddConstraintError)> {
+        &self.errors
+    }
+}
+
+/// Edge of the rectangle of a UI element.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    fn name(&self) -> &'static str {
+        match *self {
+            Edge::Left => "left_bound",
+            Edge::Right => "right_bound",
+            Edge::Top => "upper_bound",
+            Edge::Bottom => "lower_bound",
+        }
+    }
+}
+
+/// Linear combination of the edges of UI elements.
+///
+/// Built up from `edge`, `width`, `height` and `viewport` with `+`, `-` and `*`,
+/// like `edge(b, Edge::Left) - 8.0`.
+#[derive(Clone, Debug, Default)]
+pub struct Expression {
+    terms: Vec<(KeyId, Edge, f64)>,
+    constant: f64,
+}
+
+/// The `edge` of `entity`.
+pub fn edge(entity: Entity, edge: Edge) -> Expression {
+    Expression {
+        terms: vec![(KeyId::Entity(entity), edge, 1.0)],
+        constant: 0.0,
+    }
+}
+
+/// Width of `entity`, same as its right edge minus its left edge.
+pub fn width(entity: Entity) -> Expression {
+    edge(entity, Edge::Right) - edge(entity, Edge::Left)
+}
+
+/// Height of `entity`, same as its bottom edge minus its top edge.
+pub fn height(entity: Entity) -> Expression {
+    edge(entity, Edge::Bottom) - edge(entity, Edge::Top)
+}
+
+/// The `edge` of the screen.
+pub fn viewport(edge: Edge) -> Expression {
+    Expression {
+        terms: vec![(KeyId::Context, edge, 1.0)],
+        constant: 0.0,
+    }
+}
+
+impl From<f64> for Expression {
+    fn from(constant: f64) -> Expression {
+        Expression {
+            terms: Vec::new(),
+            constant: constant,
+        }
+    }
+}
+
+impl Add<Expression> for Expression {
+    type Output = Expression;
+    fn add(mut self, other: Expression) -> Expression {
+        self.terms.extend(other.terms);
+        self.constant += other.constant;
+        self
+    }
+}
+
+impl Sub<Expression> for Expression {
+    type Output = Expression;
+    fn sub(self, other: Expression) -> Expression {
+        self + -other
+    }
+}
+
+impl Add<f64> for Expression {
+    type Output = Expression;
+    fn add(mut self, constant: f64) -> Expression {
+        self.constant += constant;
+        self
+    }
+}
+
+impl Sub<f64> for Expression {
+    type Output = Expression;
+    fn sub(mut self, constant: f64) -> Expression {
+        self.constant -= constant;
+        self
+    }
+}
+
+impl Mul<f64> for Expression {
+    type Output = Expression;
+    fn mul(mut self, scale: f64) -> Expression {
+        for term in &mut self.terms {
+            term.2 *= scale;
+        }
+        self.constant *= scale;
+        self
+    }
+}
+
+impl Neg for Expression {
+    type Output = Expression;
+    fn neg(self) -> Expression {
+        self * -1.0
+    }
+}
+
+/// How the two sides of a `Constraint` relate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Relation {
+    Equal,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+/// `lhs` has to relate to `rhs` as the `relation` says, how hard the solver tries is up
+/// to the `strength`. See `strength` for the common ones.
+#[derive(Clone, Debug)]
+pub struct Constraint {
+    pub lhs: Expression,
+    pub relation: Relation,
+    pub rhs: Expression,
+    pub strength: f64,
+}
+
+impl Constraint {
+    pub fn new<L, R>(lhs: L, relation: Relation, rhs: R, strength: f64) -> Self
+        where L: Into<Expression>, R: Into<Expression>,
+    {
+        Constraint {
+            lhs: lhs.into(),
+            relation: relation,
+            rhs: rhs.into(),
+            strength: strength,
+        }
+    }
+
+    /// `lhs == rhs`
+    pub fn equal<L, R>(lhs: L, rhs: R, strength: f64) -> Self
+        where L: Into<Expression>, R: Into<Expression>,
+    {
+        Constraint::new(lhs, Relation::Equal, rhs, strength)
+    }
+
+    /// `lhs <= rhs`
+    pub fn less_or_equal<L, R>(lhs: L, rhs: R, strength: f64) -> Self
+        where L: Into<Expression>, R: Into<Expression>,
+    {
+        Constraint::new(lhs, Relation::LessOrEqual, rhs, strength)
+    }
+
+    /// `lhs >= rhs`
+    pub fn greater_or_equal<L, R>(lhs: L, rhs: R, strength: f64) -> Self
+        where L: Into<Expression>, R: Into<Expression>,
+    {
+        Constraint::new(lhs, Relation::GreaterOrEqual, rhs, strength)
+    }
+}
+
+/// Constraints between the edges of UI elements that the `SolverSystem` keeps satisfied.
+///
+/// Constraints can refer to any element, the entity holding them only decides when they
+/// go away again.
+#[derive(Clone, Debug, Default)]
+pub struct Constraints(pub Vec<Constraint>);
+
+impl Component for Constraints {
+    type Storage = TrackStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Solves the `Constraints` of the UI on top of what the `PositionSystem` computed.
+///
+/// Every element that shows up in a constraint weakly holds on to its `AbsolutePosition`,
+/// so only the edges that are constrained move. The solved edges go into `Changes`, which
+/// the `ChangedSystem` then writes into `AbsolutePosition`.
+///
+/// Children of a solved element are still laid out against where the `PositionSystem` put
+/// it, so constraints work best on elements that don't have any.
+pub struct SolverSystem {
+    solver: Solver,
+    key_map: HashMap<Key, Variable>,
+    var_map: HashMap<Variable, Key>,
+    /// Constraints that are in the solver, by the entity holding them.
+    constraints: HashMap<Entity, Vec<cassowary::Constraint>>,
+    /// Keeps the rectangles of the solved elements from turning inside out.
+    edges: HashMap<KeyId, Vec<cassowary::Constraint>>,
+    /// Elements that started being solved and still need their first suggestion.
+    fresh: Vec<Entity>,
+    /// Constraints that couldn't be added this frame.
+    errors: Vec<(cassowary::Constraint, AddConstraintError)>,
+    /// Size of the viewport in logical pixels.
+    dimensions: [f32; 2],
+}
+
+impl Default for SolverSystem {
+    fn default() -> Self {
+        SolverSystem {
+            solver: Solver::new(),
+            key_map: HashMap::new(),
+            var_map: HashMap::new(),
+            constraints: HashMap::new(),
+            edges: HashMap::new(),
+            fresh: Vec::new(),
+            errors: Vec::new(),
+            dimensions: [0.0, 0.0],
+        }
+    }
+}
+
+fn variable(variable: Variable) -> cassowary::Expression {
+    cassowary::Expression::from_term(Term {
+        variable: variable,
+        coefficient: 1.0,
+    })
+}
+
+impl SolverSystem {
+    pub fn new() -> Self {
+        SolverSystem::default()
+    }
+
+    fn add_constraint(&mut self, constraint: cassowary::Constraint) {
+        if let Err(error) = self.solver.add_constraint(constraint.clone()) {
+            self.errors.push((constraint, error));
+        }
+    }
+
+    fn remove_constraint(&mut self, constraint: &cassowary::Constraint) {
+        if self.solver.has_constraint(constraint) {
+            let _ = self.solver.remove_constraint(constraint);
+        }
+    }
+
+    /// Gets the variable for the `key`, making a new one if it doesn't exist yet.
+    fn fill_variable(&mut self, key: &Key) -> Variable {
+        if let Some(variable) = self.key_map.get(key) {
+            return *variable;
+        }
+
+        let variable = Variable::new();
+        self.key_map.insert(key.clone(), variable);
+        self.var_map.insert(variable, key.clone());
+        variable
+    }
+
+    /// Gets the left, right, top and bottom variables of `id`.
+    ///
+    /// The first time they are asked for, the edges are kept in the right order and made
+    /// editable so they can be suggested.
+    fn fill_edges(&mut self, id: &KeyId) -> [Variable; 4] {
+        let variables = [
+            self.fill_variable(&Key(id.clone(), Edge::Left.name())),
+            self.fill_variable(&Key(id.clone(), Edge::Right.name())),
+            self.fill_variable(&Key(id.clone(), Edge::Top.name())),
+            self.fill_variable(&Key(id.clone(), Edge::Bottom.name())),
+        ];
+
+        if !self.edges.contains_key(id) {
+            let strength = match *id {
+                KeyId::Context => REQUIRED - 1.0,
+                _ => WEAK,
+            };
+            for &variable in &variables {
+                let _ = self.solver.add_edit_variable(variable, strength);
+            }
+
+            let order = vec![
+                cassowary::Constraint::new(
+                    variable(variables[1]) - variables[0],
+                    RelationalOperator::GreaterOrEqual,
+                    REQUIRED,
+                ),
+                cassowary::Constraint::new(
+                    variable(variables[3]) - variables[2],
+                    RelationalOperator::GreaterOrEqual,
+                    REQUIRED,
+                ),
+            ];
+            for constraint in &order {
+                self.add_constraint(constraint.clone());
+            }
+            self.edges.insert(id.clone(), order);
+
+            if let KeyId::Entity(entity) = *id {
+                self.fresh.push(entity);
+            }
+        }
+
+        variables
+    }
+
+    /// Stops solving for the edges of `id` and forgets their variables.
+    fn remove_edges(&mut self, id: &KeyId) {
+        if let Some(order) = self.edges.remove(id) {
+            for constraint in &order {
+                self.remove_constraint(constraint);
+            }
+
+            for edge in &[Edge::Left, Edge::Right, Edge::Top, Edge::Bottom] {
+                if let Some(variable) = self.key_map.remove(&Key(id.clone(), edge.name())) {
+                    if self.solver.has_edit_variable(&variable) {
+                        let _ = self.solver.remove_edit_variable(variable);
+                    }
+                    self.var_map.remove(&variable);
+                }
+            }
+        }
+    }
+
+    /// Stops solving for the elements that none of the constraints refer to anymore.
+    fn release_edges(&mut self) {
+        let mut referenced = HashSet::new();
+        for list in self.constraints.values() {
+            for constraint in list {
+                for term in &constraint.expr().terms {
+                    if let Some(key) = self.var_map.get(&term.variable) {
+                        referenced.insert(key.0.clone());
+                    }
+                }
+            }
+        }
+
+        let released = self.edges.keys()
+            .filter(|id| **id != KeyId::Context && !referenced.contains(*id))
+            .cloned()
+            .collect::<Vec<KeyId>>();
+        for id in released {
+            self.remove_edges(&id);
+        }
+    }
+
+    fn suggest(&mut self, id: &KeyId, left: f64, right: f64, top: f64, bottom: f64) {
+        let variables = self.fill_edges(id);
+        for (&variable, &value) in variables.iter().zip(&[left, right, top, bottom]) {
+            let _ = self.solver.suggest_value(variable, value);
+        }
+    }
+
+    fn suggest_viewport(&mut self, width: f32, height: f32) {
+        self.dimensions = [width, height];
+        self.suggest(&KeyId::Context, 0.0, width as f64, 0.0, height as f64);
+    }
+
+    fn suggest_absolute(&mut self, entity: Entity, absolute: &AbsolutePosition) {
+        let (x, y) = (absolute.x as f64, absolute.y as f64);
+        let (width, height) = (absolute.width as f64, absolute.height as f64);
+        self.suggest(&KeyId::Entity(entity), x, x + width, y, y + height);
+    }
+
+    /// Turns a `Constraint` into something the solver understands, the edges it refers to
+    /// are made along the way.
+    fn convert(&mut self, constraint: &Constraint) -> cassowary::Constraint {
+        let expression = constraint.lhs.clone() - constraint.rhs.clone();
+
+        let mut terms = Vec::with_capacity(expression.terms.len());
+        for &(ref id, edge, coefficient) in &expression.terms {
+            self.fill_edges(id);
+            terms.push(Term {
+                variable: self.fill_variable(&Key(id.clone(), edge.name())),
+                coefficient: coefficient,
+            });
+        }
+
+        let relation = match constraint.relation {
+            Relation::Equal => RelationalOperator::Equal,
+            Relation::LessOrEqual => RelationalOperator::LessOrEqual,
+            Relation::GreaterOrEqual => RelationalOperator::GreaterOrEqual,
+        };
+
+        cassowary::Constraint::new(
+            cassowary::Expression::new(terms, expression.constant),
+            relation,
+            constraint.strength,
+        )
+    }
+}
+
+impl<'a> System<'a> for SolverSystem {
+    type SystemData = (
+        Entities<'a>,
+        Fetch<'a, Viewport>,
+        FetchMut<'a, Changes>,
+
+        WriteStorage<'a, Constraints>,
+        ReadStorage<'a, AbsolutePosition>,
+    );
+    fn run(&mut self, (entities, viewport, mut changes, mut constraints, absolutes): Self::SystemData) {
+        // Check if the viewport was changed
+        let (width, height) = (viewport.logical_width(), viewport.logical_height());
+        if self.dimensions[0] != width || self.dimensions[1] != height {
+            self.suggest_viewport(width, height);
+        }
+
+        // Constraints of entities that lost them go away.
+        let stale = self.constraints.keys()
+            .filter(|&&entity| !entities.is_alive(entity) || constraints.get(entity).is_none())
+            .cloned()
+            .collect::<Vec<Entity>>();
+        let mut released = !stale.is_empty();
+        for entity in stale {
+            for constraint in self.constraints.remove(&entity).unwrap_or_default() {
+                self.remove_constraint(&constraint);
+            }
+        }
+
+        // Dead elements aren't around to be solved anymore.
+        let dead = self.edges.keys()
+            .filter(|id| match **id {
+                KeyId::Entity(entity) => !entities.is_alive(entity),
+                _ => false,
+            })
+            .cloned()
+            .collect::<Vec<KeyId>>();
+        for id in dead {
+            self.remove_edges(&id);
+        }
+
+        // Replace the constraints that changed.
+        for (entity, list) in (&*entities, constraints.open().1).join() {
+            released = true;
+            for constraint in self.constraints.remove(&entity).unwrap_or_default() {
+                self.remove_constraint(&constraint);
+            }
+
+            let converted = list.0.iter()
+                .map(|constraint| self.convert(constraint))
+                .collect::<Vec<cassowary::Constraint>>();
+            for constraint in &converted {
+                self.add_constraint(constraint.clone());
+            }
+            self.constraints.insert(entity, converted);
+        }
+
+        // Elements that aren't in any constraint anymore go back to just being laid out.
+        if released {
+            self.release_edges();
+        }
+
+        // Whatever the layout came up with is where solved elements would like to be.
+        let mut suggested = Vec::new();
+        for (entity, absolute) in (&*entities, absolutes.open().1).join() {
+            if self.edges.contains_key(&KeyId::Entity(entity)) {
+                self.suggest_absolute(entity, absolute);
+                suggested.push(entity);
+            }
+        }
+        for entity in self.fresh.drain(..).collect::<Vec<Entity>>() {
+            if !self.edges.contains_key(&KeyId::Entity(entity)) {
+                continue;
+            }
+            if let Some(absolute) = absolutes.get(entity) {
+                self.suggest_absolute(entity, absolute);
+                suggested.push(entity);
+            }
+        }
+
+        let mut changes_list = self.solver.fetch_changes().iter().cloned().collect::<Vec<(Variable, f64)>>();
+
+        // The layout overwrote every edge of the suggested elements, so the ones the
+        // solver didn't move still have to be written back.
+        let mut changed = changes_list.iter().map(|&(variable, _)| variable).collect::<HashSet<Variable>>();
+        for entity in suggested {
+            for edge in &[Edge::Left, Edge::Right, Edge::Top, Edge::Bottom] {
+                if let Some(&variable) = self.key_map.get(&Key(KeyId::Entity(entity), edge.name())) {
+                    if changed.insert(variable) {
+                        changes_list.push((variable, self.solver.get_value(variable)));
+                    }
+                }
+            }
+        }
+
+        let changes_list = changes_list.iter().map(|&(variable, change)| {
+            (
+                self.var_map.get(&variable)
+                            .unwrap_or(&Key(KeyId::Unkeyed, "Unknown"))
+                            .clone(),
+                variable,
+                change,
+            )
+        }).collect::<Vec<(Key, Variable, f64)>>();
+        changes.set_changes(changes_list);
+        changes.set_errors(self.errors.drain(..).collect());
+
+        // Reset the flags.
+        (&mut constraints).open().1.clear_flags();
+    }
+}
@@ -1,39 +1,42 @@
 
-use specs::{Entities, Fetch, Join, System, ReadStorage, WriteStorage};
+use specs::{Fetch, System, WriteStorage};
 
 use ::solver::{Changes, Key, KeyId};
-use ::class::{Display};
+use ::class::AbsolutePosition;
 
+/// Writes the edges the `SolverSystem` solved into the border box of `AbsolutePosition`.
 pub struct ChangedSystem;
 impl<'a> System<'a> for ChangedSystem {
     type SystemData = (
-        Entities<'a>,
         Fetch<'a, Changes>,
-        WriteStorage<'a, Display>,
+        WriteStorage<'a, AbsolutePosition>,
     );
-    fn run(&mut self, (entities, changes, mut positions): Self::SystemData) {
-        println!("Changes");
-        for &(ref key, ref variable, change) in changes.changes() {
-            println!("  {:?}", (&key, &variable, &change));
+    fn run(&mut self, (changes, mut absolutes): Self::SystemData) {
+        for &(ref key, _, change) in changes.changes() {
+            let change = change as f32;
             match *key {
                 Key(KeyId::Entity(entity), "left_bound") => {
-                    if let Some(position) = positions.get_mut(entity) {
-                        position.left = change;
+                    if let Some(absolute) = absolutes.get_mut(entity) {
+                        let right = absolute.x + absolute.width;
+                        absolute.x = change;
+                        absolute.width = right - change;
                     }
                 },
                 Key(KeyId::Entity(entity), "right_bound") => {
-                    if let Some(position) = positions.get_mut(entity) {
-                        position.right = change;
+                    if let Some(absolute) = absolutes.get_mut(entity) {
+                        absolute.width = change - absolute.x;
                     }
                 },
                 Key(KeyId::Entity(entity), "upper_bound") => {
-                    if let Some(position) = positions.get_mut(entity) {
-                        position.top = change;
+                    if let Some(absolute) = absolutes.get_mut(entity) {
+                        let bottom = absolute.y + absolute.height;
+                        absolute.y = change;
+                        absolute.height = bottom - change;
                     }
                 },
                 Key(KeyId::Entity(entity), "lower_bound") => {
-                    if let Some(position) = positions.get_mut(entity) {
-                        position.bottom = change;
+                    if let Some(absolute) = absolutes.get_mut(entity) {
+                        absolute.height = change - absolute.y;
                     }
                 },
                 _ => { }
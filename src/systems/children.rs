@@ -1,9 +1,37 @@
 
-use specs::{Entities, Join, System, ReadStorage, WriteStorage};
+use std::collections::HashMap;
+
+use specs::{Entities, Entity, Join, System, ReadStorage, WriteStorage};
 
 use class::{Children, Parent};
 
-pub struct ChildrenSystem;
+/// Keeps the `Children` of every element in sync with the `Parent`s pointing at it.
+///
+/// Children are added in the order they got their `Parent`, the ones that got one in the
+/// same frame go by their id. Changing the `Parent`, removing it or deleting the child
+/// takes it out of the old parent's list, and parents without any children left lose
+/// their `Children`. A `Parent` pointing at a dead entity counts as no parent, unless
+/// `despawn_orphans` is on.
+#[derive(Default)]
+pub struct ChildrenSystem {
+    /// Parent of every child from the last frame.
+    parents: HashMap<Entity, Entity>,
+    despawn_orphans: bool,
+}
+
+impl ChildrenSystem {
+    pub fn new() -> Self {
+        ChildrenSystem::default()
+    }
+
+    /// Deletes elements whose parent died, along with everything below them, like
+    /// `hierarchy::despawn_recursive` would.
+    pub fn despawn_orphans(mut self, despawn: bool) -> Self {
+        self.despawn_orphans = despawn;
+        self
+    }
+}
+
 impl<'a> System<'a> for ChildrenSystem {
     type SystemData = (
         Entities<'a>,
@@ -11,40 +39,69 @@ impl<'a> System<'a> for ChildrenSystem {
         WriteStorage<'a, Children>,
     );
     fn run(&mut self, (entities, parents, mut children): Self::SystemData) {
-        let flagged_parents = parents.open().1;
+        let mut current = HashMap::with_capacity(self.parents.len());
+        let mut added = Vec::new();
+        let mut orphans = Vec::new();
+        for (entity, parent) in (&*entities, &parents).join() {
+            if !entities.is_alive(parent.entity) {
+                orphans.push(entity);
+                continue;
+            }
+
+            current.insert(entity, parent.entity);
+            if self.parents.get(&entity) != Some(&parent.entity) {
+                added.push((entity, parent.entity));
+            }
+        }
 
-        {
-            // If an entity is a parent, then it should have children.
-            for (entity, parent) in (&*entities, flagged_parents).join() {
-                if let None = children.get(parent.entity) {
-                    children.insert(parent.entity, Children::default());
+        // Take children out of the parents they left, whether they moved, lost their
+        // `Parent` or were deleted.
+        let mut emptied = Vec::new();
+        for (child, old) in self.parents.iter() {
+            if current.get(child) == Some(old) {
+                continue;
+            }
+
+            if let Some(list) = children.get_mut(*old) {
+                list.remove(*child);
+                if list.entities().is_empty() {
+                    emptied.push(*old);
                 }
             }
+        }
+
+        for (child, parent) in added {
+            if children.get(parent).is_none() {
+                children.insert(parent, Children::default());
+            }
 
-            // Iterate over changed parents and add the entities to that parents child list.
-            for (entity, parent) in (&*entities, flagged_parents).join() {
-                let mut child_list = children.get_mut(parent.entity).unwrap();
-                child_list.push(entity);
+            let list = children.get_mut(parent).unwrap();
+            if !list.entities().contains(&child) {
+                list.push(child);
             }
         }
 
-        {
-            let mut flagged_children = (&mut children).open().1;
-
-            // Clean up children that are no longer parented to this UI.
-            for (entity, mut children) in (&*entities, flagged_children).join() {
-                let mut remove = Vec::new();
-                for (index, child) in children.entities().iter().enumerate() {
-                    match parents.get(*child) {
-                        Some(parent) if parent.entity != entity => { },
-                        _ => remove.push(index),
-                    }
-                }
+        for parent in emptied {
+            let empty = children.get(parent).map_or(false, |list| list.entities().is_empty());
+            if empty {
+                children.remove(parent);
             }
         }
 
-        for (entity, child) in (&*entities, &children).join() {
-            println!("{:?} contains {:?}", entity, child.entities());
+        if self.despawn_orphans && !orphans.is_empty() {
+            let mut tree: HashMap<Entity, Vec<Entity>> = HashMap::new();
+            for (&child, &parent) in current.iter() {
+                tree.entry(parent).or_insert_with(Vec::new).push(child);
+            }
+
+            while let Some(orphan) = orphans.pop() {
+                if let Some(list) = tree.remove(&orphan) {
+                    orphans.extend(list);
+                }
+                entities.delete(orphan);
+            }
         }
+
+        self.parents = current;
     }
 }
@@ -1,2 +1,6 @@
 
+pub mod children;
 pub mod position;
+pub mod reset;
+#[cfg(feature = "solver")]
+pub mod changed;
@@ -1,77 +1,747 @@
 
-use specs::{Component, System, Entities, Fetch, WriteStorage, ReadStorage, Join};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::mem;
+use std::usize;
 
-use hibitset::{BitSetLike, BitSetOr};
+use specs::{System, Entities, Entity, Fetch, WriteStorage, ReadStorage, Join};
 
-use class::{Coordinate, Position, PositionKind, Bounds, AbsolutePosition, Viewport};
+use hibitset::{BitSet, BitSetLike, BitSetOr};
 
-fn relative_parent(parent: f32, coordinate: Coordinate) -> f32 {
+use class::{Children, Coordinate, Flow, Layout, LayoutItem, Parent, Position, PositionKind, Text};
+use class::{Bounds, AbsolutePosition, Display, SafeArea, UiScale, Units, Viewport};
+use layout::{FlowLayout, Insets, LayoutAlgorithm, LayoutChild, Layouts, Limits, Rect, Size};
+
+fn relative_parent(parent: f32, coordinate: Coordinate, units: &Units) -> f32 {
+    coordinate.resolve(parent, units)
+}
+
+/// Resolves `coordinate` if it doesn't need the size of the parent.
+fn absolute(coordinate: Option<&Coordinate>, units: &Units) -> Option<f32> {
     match coordinate {
-        Coordinate::Percent(percent) => parent as f32 * percent,
-        Coordinate::Pixel(pixel) => pixel,
+        Some(coordinate) if coordinate.is_absolute() => Some(coordinate.resolve(0.0, units)),
+        _ => None,
+    }
+}
+
+/// Units of `entity`, its font size is the one of its own `Text` or the closest one
+/// above it. The screen itself has the default font size. Font sizes are in `Pixel`s, so
+/// they get scaled by `pixel`.
+fn units_of(screen: &Rect, pixel: f32, fonts: &HashMap<Entity, f32>, entity: Option<Entity>) -> Units {
+    let default = Units::default();
+    Units {
+        viewport: Size {
+            width: screen.width,
+            height: screen.height,
+        },
+        font_size: entity
+            .and_then(|entity| fonts.get(&entity).cloned())
+            .unwrap_or(default.font_size) * pixel,
+        pixel: pixel,
+    }
+}
+
+/// Resolves the minimum, maximum and aspect ratio of the `Bounds` inside of `base`.
+///
+/// Without a `base` there is nothing to take a `Percent` of yet, so only the limits that
+/// don't need one are kept.
+fn resolve_limits(base: Option<&Rect>, bounds: Option<&Bounds>, units: &Units) -> Limits {
+    let mut limits = Limits::default();
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return limits,
+    };
+
+    let resolve = |coordinate: &Option<Coordinate>, parent: Option<f32>| match parent {
+        Some(parent) => coordinate.clone().map(|coordinate| relative_parent(parent, coordinate, units)),
+        None => absolute(coordinate.as_ref(), units),
+    };
+    let width = base.map(|base| base.width);
+    let height = base.map(|base| base.height);
+
+    if let Some(min) = resolve(&bounds.min_width, width) {
+        limits.min.width = min;
+    }
+    if let Some(max) = resolve(&bounds.max_width, width) {
+        limits.max.width = max;
+    }
+    if let Some(min) = resolve(&bounds.min_height, height) {
+        limits.min.height = min;
+    }
+    if let Some(max) = resolve(&bounds.max_height, height) {
+        limits.max.height = max;
+    }
+    limits.aspect_ratio = bounds.aspect_ratio;
+    limits
+}
+
+/// Whether the aspect ratio of the `Bounds` should follow the height over the width.
+fn height_leads(bounds: Option<&Bounds>) -> bool {
+    bounds.map_or(false, |bounds| bounds.height.is_some() && bounds.width.is_none())
+}
+
+/// Resolves the `Bounds` of an element into a width and height inside of `base`.
+///
+/// Whatever the bounds leave out is the size of the `content`, and the result is kept
+/// inside of the limits of the bounds.
+fn resolve_bounds(base: &Rect, bounds: Option<&Bounds>, content: Size, units: &Units) -> (f32, f32) {
+    let width = bounds.and_then(|bounds| bounds.width.clone())
+        .map(|width| relative_parent(base.width, width, units))
+        .unwrap_or(content.width);
+    let height = bounds.and_then(|bounds| bounds.height.clone())
+        .map(|height| relative_parent(base.height, height, units))
+        .unwrap_or(content.height);
+
+    let size = resolve_limits(Some(base), bounds, units).apply(Size {
+        width: width,
+        height: height,
+    }, height_leads(bounds));
+    (size.width, size.height)
+}
+
+/// Resolves the `x`/`y` offsets of a `Position` inside of `base`.
+fn resolve_offset(base: &Rect, position: &Position, units: &Units) -> (f32, f32) {
+    let x = relative_parent(base.width, position.x.clone().unwrap_or(Coordinate::Pixel(0.0)), units);
+    let y = relative_parent(base.height, position.y.clone().unwrap_or(Coordinate::Pixel(0.0)), units);
+    (x, y)
+}
+
+/// Places an element that is `width` by `height` inside of `base`, so the `pivot` of the
+/// `Position` lands on its `anchor` and is then moved by `offset`.
+fn resolve_anchor(base: &Rect, position: &Position, offset: (f32, f32), width: f32, height: f32) -> Rect {
+    let (x, y) = offset;
+    Rect {
+        x: base.x + base.width * position.anchor.x - width * position.pivot.x + x,
+        y: base.y + base.height * position.anchor.y - height * position.pivot.y + y,
+        width: width,
+        height: height,
+    }
+}
+
+/// Whether the element is placed by the parent's layout, elements without a `Position`
+/// are `Relative` like the default one.
+fn is_relative(position: Option<&Position>) -> bool {
+    match position {
+        Some(&Position { kind: PositionKind::Relative, .. }) | None => true,
+        _ => false,
+    }
+}
+
+/// Storages needed to lay out the children of an element.
+struct Context<'s, 'a: 's> {
+    screen: Rect,
+    /// Part of the screen that isn't cut off, see `SafeArea`.
+    safe_area: Rect,
+    /// Size of a `Pixel`, see `UiScale`.
+    pixel: f32,
+    registry: &'s Layouts,
+    /// Size of the content of every element, see `measure`.
+    measured: &'s HashMap<Entity, Size>,
+    /// Font size of every element, see `units_of`.
+    fonts: &'s HashMap<Entity, f32>,
+    texts: &'s ReadStorage<'a, Text>,
+    layouts: &'s ReadStorage<'a, Layout>,
+    items: &'s ReadStorage<'a, LayoutItem>,
+    positions: &'s ReadStorage<'a, Position>,
+    bounds: &'s ReadStorage<'a, Bounds>,
+    displays: &'s ReadStorage<'a, Display>,
+    safe_areas: &'s ReadStorage<'a, SafeArea>,
+}
+
+impl<'s, 'a> Context<'s, 'a> {
+    fn content(&self, entity: Entity) -> Size {
+        self.measured.get(&entity).cloned().unwrap_or_default()
+    }
+
+    fn units(&self, entity: Entity) -> Units {
+        units_of(&self.screen, self.pixel, self.fonts, Some(entity))
+    }
+
+    /// Margin of `entity` inside of `base`, `Percent` is nothing without one.
+    fn margin(&self, entity: Entity, base: Option<&Rect>) -> Insets {
+        let (width, height) = base.map_or((0.0, 0.0), |base| (base.width, base.height));
+        self.displays.get(entity)
+            .map(|display| display.margin.resolve(width, height, &self.units(entity)))
+            .unwrap_or_default()
+    }
+
+    /// Padding of `entity` inside of `base`, `Percent` is nothing without one.
+    fn padding(&self, entity: Entity, base: Option<&Rect>) -> Insets {
+        let (width, height) = base.map_or((0.0, 0.0), |base| (base.width, base.height));
+        self.displays.get(entity)
+            .map(|display| display.padding.resolve(width, height, &self.units(entity)))
+            .unwrap_or_default()
+    }
+
+    /// Hands `child` to a layout, with its limits resolved inside of the `parent` if it
+    /// is known.
+    ///
+    /// Layouts see the margin as part of the child, so it is added onto the `size` and
+    /// the limits.
+    fn layout_child(&self, child: Entity, size: Size, parent: Option<&Rect>) -> LayoutChild<'s> {
+        let bounds = self.bounds.get(child);
+        let margin = self.margin(child, parent);
+        let mut limits = resolve_limits(parent, bounds, &self.units(child));
+        limits.min.width += margin.horizontal();
+        limits.min.height += margin.vertical();
+        limits.max.width += margin.horizontal();
+        limits.max.height += margin.vertical();
+        if margin != Insets::default() {
+            // The ratio is of the border box, it gets kept once the margin is gone.
+            limits.aspect_ratio = None;
+        }
+
+        LayoutChild {
+            entity: child,
+            size: Size {
+                width: size.width + margin.horizontal(),
+                height: size.height + margin.vertical(),
+            },
+            fixed_width: bounds.map_or(false, |bounds| bounds.width.is_some()),
+            fixed_height: bounds.map_or(false, |bounds| bounds.height.is_some()),
+            limits: limits,
+            item: self.items.get(child),
+        }
+    }
+}
+
+/// Measures how big `entity` would like to be, bottom up, so the children have to be
+/// measured already.
+///
+/// Bounds that don't need the size of the parent are taken as they are. Everything else
+/// is the size of the content, which is the `Text` for leaves and whatever the `Layout`
+/// needs for the `Relative` children otherwise, plus the padding. Either way the size is
+/// kept inside of the limits. There is nothing to take a `Percent` of yet.
+fn measure(entity: Entity, children: &[Entity], context: &Context) -> Size {
+    let units = context.units(entity);
+    let content = match context.texts.get(entity) {
+        Some(text) => text.measure(&units),
+        None => {
+            let children = children.iter()
+                .cloned()
+                .filter(|&child| is_relative(context.positions.get(child)))
+                .map(|child| context.layout_child(child, context.content(child), None))
+                .collect::<Vec<LayoutChild>>();
+
+            if children.is_empty() {
+                Size::default()
+            } else {
+                context.layouts.get(entity)
+                    .and_then(|layout| context.registry.measure(layout, &units, &children))
+                    .unwrap_or_else(|| FlowLayout.measure(&Flow, &units, &children))
+            }
+        },
+    };
+
+    let padding = context.padding(entity, None);
+    let content = Size {
+        width: content.width + padding.horizontal(),
+        height: content.height + padding.vertical(),
+    };
+
+    let bounds = context.bounds.get(entity);
+    let size = Size {
+        width: absolute(bounds.and_then(|bounds| bounds.width.as_ref()), &units).unwrap_or(content.width),
+        height: absolute(bounds.and_then(|bounds| bounds.height.as_ref()), &units).unwrap_or(content.height),
+    };
+    resolve_limits(None, bounds, &units).apply(size, height_leads(bounds))
+}
+
+/// Finds the spot each of the `Relative` children takes up in the parent's layout.
+///
+/// Elements without a `Layout`, or with one nothing is registered for, use a `Flow`.
+fn slots(layout: Option<&Layout>, parent: &Rect, units: &Units, relative: &[Entity], context: &Context) -> Vec<Rect> {
+    let children = relative.iter()
+        .map(|&child| {
+            let bounds = context.bounds.get(child);
+            let (width, height) = resolve_bounds(parent, bounds, context.content(child), &context.units(child));
+            context.layout_child(child, Size {
+                width: width,
+                height: height,
+            }, Some(parent))
+        })
+        .collect::<Vec<LayoutChild>>();
+
+    layout
+        .and_then(|layout| context.registry.arrange(layout, parent, units, &children))
+        .unwrap_or_else(|| FlowLayout.arrange(&Flow, parent, units, &children))
+}
+
+/// Lays out `children` inside of `parent`.
+///
+/// `parent` is the content box of the parent. `Relative` children are placed by the
+/// parent's `Layout`, anchored inside of the spot it gives them and then moved by their
+/// offset. `Free` children are taken out of the layout and `Absolute` children only care
+/// about the screen, or the safe area with a `SafeArea`. Each of them is moved in by its
+/// margin.
+fn arrange(parent_entity: Option<Entity>, parent: &Rect, children: &[Entity], context: &Context) -> Vec<Rect> {
+    let default = Position::default();
+    let position_of = |child: Entity| context.positions.get(child).unwrap_or(&default);
+
+    let relative = children.iter()
+        .cloned()
+        .filter(|&child| is_relative(context.positions.get(child)))
+        .collect::<Vec<Entity>>();
+    let layout = parent_entity.and_then(|entity| context.layouts.get(entity));
+    let units = units_of(&context.screen, context.pixel, context.fonts, parent_entity);
+    let mut placed = slots(layout, parent, &units, &relative, context).into_iter();
+
+    let mut rects = Vec::with_capacity(children.len());
+    for &child in children {
+        let position = position_of(child);
+        let bounds = context.bounds.get(child);
+        let units = context.units(child);
+        let rect = match position.kind {
+            PositionKind::Free => {
+                let area = parent.inset(&context.margin(child, Some(parent)));
+                let (width, height) = resolve_bounds(parent, bounds, context.content(child), &units);
+                resolve_anchor(&area, position, resolve_offset(parent, position, &units), width, height)
+            },
+            PositionKind::Relative => {
+                // Layouts should keep to the limits already, this catches the ones that
+                // don't.
+                let slot = placed.next().unwrap_or_default().inset(&context.margin(child, Some(parent)));
+                let size = resolve_limits(Some(parent), bounds, &units).apply(Size {
+                    width: slot.width,
+                    height: slot.height,
+                }, height_leads(bounds));
+                resolve_anchor(&slot, position, resolve_offset(parent, position, &units), size.width, size.height)
+            },
+            PositionKind::Absolute => {
+                let screen = match context.safe_areas.get(child) {
+                    Some(_) => &context.safe_area,
+                    None => &context.screen,
+                };
+                let area = screen.inset(&context.margin(child, Some(screen)));
+                let (width, height) = resolve_bounds(screen, bounds, context.content(child), &units);
+                resolve_anchor(&area, position, resolve_offset(screen, position, &units), width, height)
+            },
+        };
+
+        rects.push(rect);
+    }
+
+    rects
+}
+
+/// Puts `item` into the bucket for `level`, unless it has been queued before.
+fn enqueue<T: Copy + Eq + Hash>(levels: &mut Vec<Vec<T>>, queued: &mut HashSet<T>, level: usize, item: T) {
+    if queued.insert(item) {
+        while levels.len() <= level {
+            levels.push(Vec::new());
+        }
+        levels[level].push(item);
     }
 }
 
+fn any(set: &BitSet) -> bool {
+    set.iter().next().is_some()
+}
+
 /// Solver for computing positions and bounds into the absolute position that the UI
 /// will be on the screen.
-pub struct PositionSystem;
+///
+/// Layout happens in two passes. First elements are measured from the bottom up to find
+/// the size of their content, then the hierarchy is arranged from the top down, so a
+/// parent is always resolved before its children, which go inside of its padding.
+///
+/// Both passes only go over what changed. A changed element is measured again, along
+/// with the ancestors that change size because of it. The siblings of anything that
+/// changed are arranged again, along with the subtrees of the elements that moved. The
+/// hierarchy itself is only rebuilt when a `Parent`, `Children` or `AbsolutePosition`
+/// is added, changed or removed. Resizing the `Viewport` or changing the `UiScale` lays
+/// out everything again.
+///
+/// The change flags of the UI are left alone for other systems to read, the
+/// `ResetSystem` clears them at the end of the frame. The flags of `AbsolutePosition`
+/// are cleared here instead, so they last from one layout to the next.
+#[derive(Default)]
+pub struct PositionSystem {
+    /// Size every element would like to be, see `measure`.
+    measured: HashMap<Entity, Size>,
+    /// Border box and padding every element was last placed with.
+    placed: HashMap<Entity, (Rect, Insets)>,
+    /// Children of every element, in order.
+    tree: HashMap<Entity, Vec<Entity>>,
+    parents: HashMap<Entity, Entity>,
+    /// Elements laid out in the screen.
+    roots: Vec<Entity>,
+    /// Pre-order of the hierarchy, siblings in the order of their `Children`.
+    order: Vec<Entity>,
+    /// Amount of ancestors every element has.
+    depths: HashMap<Entity, usize>,
+    /// Font size of every element, see `units_of`.
+    fonts: HashMap<Entity, f32>,
+    /// Viewport the UI was last laid out in.
+    viewport: Option<Viewport>,
+    /// Size of a `Pixel` the UI was last laid out with.
+    pixel: Option<f32>,
+}
+
+impl PositionSystem {
+    pub fn new() -> Self {
+        PositionSystem::default()
+    }
+
+    /// Level the children of `parent` are arranged at.
+    fn level(&self, parent: Option<Entity>) -> usize {
+        parent.and_then(|parent| self.depths.get(&parent)).map_or(0, |depth| depth + 1)
+    }
+
+    /// Rebuilds the hierarchy, returning the parents whose children changed, `None`
+    /// being the screen.
+    fn rebuild<'a>(
+        &mut self,
+        entities: &Entities<'a>,
+        parents: &ReadStorage<'a, Parent>,
+        children: &ReadStorage<'a, Children>,
+        absolutes: &WriteStorage<'a, AbsolutePosition>,
+    ) -> Vec<Option<Entity>> {
+        // Elements without a (living) parent are laid out in the screen.
+        let mut roots = Vec::new();
+        for (entity, _) in (&**entities, absolutes).join() {
+            match parents.get(entity) {
+                Some(parent) if entities.is_alive(parent.entity) => { },
+                _ => roots.push(entity),
+            }
+        }
+
+        let mut tree: HashMap<Entity, Vec<Entity>> = HashMap::new();
+        let mut parent_of = HashMap::new();
+        for (entity, parent) in (&**entities, parents).join() {
+            if entities.is_alive(parent.entity) {
+                tree.entry(parent.entity).or_insert_with(Vec::new).push(entity);
+                parent_of.insert(entity, parent.entity);
+            }
+        }
+
+        // Children follow the order of the parent's `Children`, anything it doesn't know
+        // about yet goes to the end.
+        for (parent, list) in tree.iter_mut() {
+            let order = children.get(*parent);
+            list.sort_by_key(|child| {
+                let index = order
+                    .and_then(|order| order.index_of(*child))
+                    .unwrap_or(usize::MAX);
+                (index, child.id())
+            });
+        }
+
+        let mut order = Vec::new();
+        let mut depths = HashMap::new();
+        let mut stack = roots.iter().rev().map(|&root| (root, 0)).collect::<Vec<(Entity, usize)>>();
+        while let Some((entity, depth)) = stack.pop() {
+            if depths.contains_key(&entity) {
+                continue;
+            }
+
+            order.push(entity);
+            depths.insert(entity, depth);
+            if let Some(list) = tree.get(&entity) {
+                stack.extend(list.iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+
+        let mut changed = Vec::new();
+        if roots != self.roots {
+            changed.push(None);
+        }
+        for (parent, list) in tree.iter() {
+            if self.tree.get(parent) != Some(list) {
+                changed.push(Some(*parent));
+            }
+        }
+        for parent in self.tree.keys() {
+            if !tree.contains_key(parent) {
+                changed.push(Some(*parent));
+            }
+        }
+
+        // Forget about everything that isn't laid out anymore.
+        self.measured.retain(|entity, _| depths.contains_key(entity));
+        self.placed.retain(|entity, _| depths.contains_key(entity));
+        self.fonts.retain(|entity, _| depths.contains_key(entity));
+
+        self.tree = tree;
+        self.parents = parent_of;
+        self.roots = roots;
+        self.order = order;
+        self.depths = depths;
+        changed
+    }
+
+    /// Passes font sizes down the subtree of `entity`, flagging the elements whose font
+    /// size changed in `changed`.
+    fn update_fonts<'a>(&mut self, entity: Entity, texts: &ReadStorage<'a, Text>, changed: &mut BitSet) {
+        let mut stack = vec![entity];
+        while let Some(entity) = stack.pop() {
+            let inherited = self.parents.get(&entity).and_then(|parent| self.fonts.get(parent).cloned());
+            let font = texts.get(entity).map(|text| text.size).or(inherited);
+            let old = match font {
+                Some(size) => self.fonts.insert(entity, size),
+                None => self.fonts.remove(&entity),
+            };
+            if old != font {
+                changed.add(entity.id());
+            }
+
+            if let Some(list) = self.tree.get(&entity) {
+                stack.extend(list.iter().cloned());
+            }
+        }
+    }
+}
+
 impl<'a> System<'a> for PositionSystem {
     type SystemData = (
         Entities<'a>,
         Fetch<'a, Viewport>,
+        Fetch<'a, UiScale>,
+        Fetch<'a, Layouts>,
 
-        WriteStorage<'a, Position>,
-        WriteStorage<'a, Bounds>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Children>,
+        ReadStorage<'a, Text>,
+        ReadStorage<'a, Layout>,
+        ReadStorage<'a, LayoutItem>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Bounds>,
+        ReadStorage<'a, Display>,
+        ReadStorage<'a, SafeArea>,
         WriteStorage<'a, AbsolutePosition>,
     );
-    fn run(&mut self, mut data: Self::SystemData) {
-        let (entities, viewport, mut positions, mut bounds, mut absolutes) = data;
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, viewport, scale, registry, parents, children, texts, layouts, items, positions, bounds, displays, safe_areas, mut absolutes) = data;
+
+        // Did the hierarchy change?
+        let rebuild = self.depths.is_empty() || {
+            let parents = parents.open().1;
+            let children = children.open().1;
+            let absolutes = absolutes.open().1;
+            any(parents.open().0) || any(parents.removed()) ||
+            any(children.open().0) || any(children.removed()) ||
+            any(absolutes.inserted()) || any(absolutes.removed())
+        };
+
+        // Did the position, the bounds, the box model, the layout or the content change?
+        // Taking one of them away changes the element too, like removing the `Bounds`
+        // sizing it back to its content.
+        let mut dirty = BitSet::new();
+        {
+            let flags = [
+                positions.open().1.open().0,
+                bounds.open().1.open().0,
+                displays.open().1.open().0,
+                layouts.open().1.open().0,
+                items.open().1.open().0,
+                texts.open().1.open().0,
+                positions.open().1.removed(),
+                bounds.open().1.removed(),
+                displays.open().1.removed(),
+                layouts.open().1.removed(),
+                items.open().1.removed(),
+                texts.open().1.removed(),
+                safe_areas.open().1.open().0,
+                safe_areas.open().1.removed(),
+                absolutes.open().1.inserted(),
+            ];
+            for &flags in flags.iter() {
+                for (entity, _) in (&*entities, flags).join() {
+                    dirty.add(entity.id());
+                }
+            }
+        }
 
         // Frame has gone by, should clear the absolute's flags.
         (&mut absolutes).open().1.clear_flags();
 
-        // Did either the position or the bounds change?
-        let filter = {
-            let positions_mask = positions.open().1.open().0;
-            let bounds_mask = bounds.open().1.open().0;
-            BitSetOr(positions_mask.clone(), bounds_mask.clone())
-        };
+        let screen = Rect::viewport(&viewport);
+        let safe_area = Rect::safe_area(&viewport);
+        let pixel = scale.factor(&viewport);
 
-        for (entity, _, absolute) in (&*entities, &filter, &mut absolutes).join() {
-            let (x, y) = match positions.get(entity) {
-                Some(position) => match position.kind { 
-                    PositionKind::Free => unimplemented!(),
-                    PositionKind::Relative => unimplemented!(),
-                    PositionKind::Absolute => {
-                        let x = relative_parent(viewport.width as f32, position.x.clone().unwrap_or(Coordinate::Pixel(0.0)));
-                        let y = relative_parent(viewport.height as f32, position.y.clone().unwrap_or(Coordinate::Pixel(0.0)));
-                        (x, y)
-                    },
-                },
-                None => (0.0, 0.0),
-            };
+        let mut measure_levels: Vec<Vec<Entity>> = Vec::new();
+        let mut measure_queued = HashSet::new();
+        let mut arrange_levels: Vec<Vec<Option<Entity>>> = Vec::new();
+        let mut arrange_queued = HashSet::new();
 
-            let (width, height) = {
-                match bounds.get(entity) {
-                    Some(bounds) => {
-                        let width = relative_parent(viewport.width as f32, bounds.width.clone().unwrap_or(Coordinate::Pixel(100.0)));
-                        let height = relative_parent(viewport.height as f32, bounds.height.clone().unwrap_or(Coordinate::Pixel(100.0)));
-                        (width, height)
-                    },
-                    None => (100.0, 100.0),
+        if rebuild {
+            for parent in self.rebuild(&entities, &parents, &children, &absolutes) {
+                if let Some(entity) = parent {
+                    // Parents that aren't laid out anymore have nothing left to arrange.
+                    match self.depths.get(&entity) {
+                        Some(&depth) => enqueue(&mut measure_levels, &mut measure_queued, depth, entity),
+                        None => continue,
+                    }
                 }
-            };
+                let level = self.level(parent);
+                enqueue(&mut arrange_levels, &mut arrange_queued, level, parent);
+            }
+
+            // Anything new has never been measured.
+            for &entity in &self.order {
+                if !self.measured.contains_key(&entity) {
+                    dirty.add(entity.id());
+                }
+            }
+
+            let roots = self.roots.clone();
+            for root in roots {
+                self.update_fonts(root, &texts, &mut dirty);
+            }
+        } else {
+            // Font sizes get passed down from a changed `Text`.
+            let mut changed = Vec::new();
+            {
+                let texts = texts.open().1;
+                for (entity, _) in (&*entities, BitSetOr(texts.open().0, texts.removed())).join() {
+                    if self.depths.contains_key(&entity) {
+                        changed.push(entity);
+                    }
+                }
+            }
+            for entity in changed {
+                self.update_fonts(entity, &texts, &mut dirty);
+            }
+        }
 
-            absolute.x = x;
-            absolute.y = y;
-            absolute.width = width;
-            absolute.height = height;
+        // Anything could be sized by the screen, through a `Percent` of a root, the
+        // viewport units or the size of a `Pixel`, so a resize lays everything out again.
+        if self.viewport.as_ref() != Some(&*viewport) || self.pixel != Some(pixel) {
+            for &entity in &self.order {
+                dirty.add(entity.id());
+            }
+            self.viewport = Some(viewport.clone());
+            self.pixel = Some(pixel);
+        }
 
-            println!("{:?} = {:?}", entity, absolute);
+        for &entity in &self.order {
+            if dirty.contains(entity.id()) {
+                let depth = self.depths[&entity];
+                enqueue(&mut measure_levels, &mut measure_queued, depth, entity);
+            }
         }
 
-        // Reset the flags.
-        (&mut positions).open().1.clear_flags();
-        (&mut bounds).open().1.clear_flags();
+        // Measure pass, deepest elements first. Elements that changed size or changed
+        // themselves get their parent measured again and their siblings arranged again.
+        for level in (0..measure_levels.len()).rev() {
+            let list = mem::replace(&mut measure_levels[level], Vec::new());
+            for entity in list {
+                let size = {
+                    let context = Context {
+                        screen: screen,
+                        safe_area: safe_area,
+                        pixel: pixel,
+                        registry: &registry,
+                        measured: &self.measured,
+                        fonts: &self.fonts,
+                        texts: &texts,
+                        layouts: &layouts,
+                        items: &items,
+                        positions: &positions,
+                        bounds: &bounds,
+                        displays: &displays,
+                        safe_areas: &safe_areas,
+                    };
+                    let list = self.tree.get(&entity).map(|list| &list[..]).unwrap_or(&[]);
+                    measure(entity, list, &context)
+                };
+
+                let resized = self.measured.insert(entity, size) != Some(size);
+                if resized || dirty.contains(entity.id()) {
+                    let parent = self.parents.get(&entity).cloned();
+                    if let Some(parent) = parent {
+                        enqueue(&mut measure_levels, &mut measure_queued, level - 1, parent);
+                    }
+                    let level = self.level(parent);
+                    enqueue(&mut arrange_levels, &mut arrange_queued, level, parent);
+                }
+            }
+        }
+
+        // Arrange pass, shallowest lists of siblings first. Elements that moved get their
+        // children arranged again.
+        let mut level = 0;
+        while level < arrange_levels.len() {
+            let lists = mem::replace(&mut arrange_levels[level], Vec::new());
+            for parent_entity in lists {
+                let groups = match parent_entity {
+                    // Roots with a `SafeArea` are laid out on their own, inside of it.
+                    None => {
+                        let (safe, rest): (Vec<Entity>, Vec<Entity>) = self.roots.iter()
+                            .cloned()
+                            .partition(|&root| safe_areas.get(root).is_some());
+                        vec![(screen, rest), (safe_area, safe)]
+                    },
+                    Some(entity) => match (self.placed.get(&entity), self.tree.get(&entity)) {
+                        (Some(&(rect, padding)), Some(list)) => vec![(rect.inset(&padding), list.clone())],
+                        // Not placed yet, it gets to its children when it is.
+                        _ => continue,
+                    },
+                };
+
+                for (parent, list) in groups {
+                    let rects = {
+                        let context = Context {
+                            screen: screen,
+                            safe_area: safe_area,
+                            pixel: pixel,
+                            registry: &registry,
+                            measured: &self.measured,
+                            fonts: &self.fonts,
+                            texts: &texts,
+                            layouts: &layouts,
+                            items: &items,
+                            positions: &positions,
+                            bounds: &bounds,
+                            displays: &displays,
+                            safe_areas: &safe_areas,
+                        };
+                        arrange(parent_entity, &parent, &list, &context)
+                    };
+
+                    for (entity, rect) in list.into_iter().zip(rects) {
+                        let units = units_of(&screen, pixel, &self.fonts, Some(entity));
+                        let padding = displays.get(entity)
+                            .map(|display| display.padding.resolve(parent.width, parent.height, &units))
+                            .unwrap_or_default();
+
+                        let moved = self.placed.get(&entity) != Some(&(rect, padding));
+                        if moved {
+                            self.placed.insert(entity, (rect, padding));
+                        }
+
+                        let stale = absolutes.get(entity).map_or(false, |absolute| {
+                            Rect::absolute(absolute) != rect || absolute.padding != padding
+                        });
+                        if stale {
+                            let absolute = absolutes.get_mut(entity).unwrap();
+                            absolute.x = rect.x;
+                            absolute.y = rect.y;
+                            absolute.width = rect.width;
+                            absolute.height = rect.height;
+                            absolute.padding = padding;
+                        }
+
+                        // Children go inside of the padding.
+                        if (moved || dirty.contains(entity.id())) && self.tree.contains_key(&entity) {
+                            enqueue(&mut arrange_levels, &mut arrange_queued, level + 1, Some(entity));
+                        }
+                    }
+                }
+            }
+            level += 1;
+        }
+
+        // Later elements in the pre-order are drawn over earlier ones, unless the
+        // `Position` says otherwise. Only needed when the order or a `Position` changed.
+        if rebuild || any(positions.open().1.open().0) {
+            for (index, &entity) in self.order.iter().enumerate() {
+                let z = positions.get(entity).and_then(|position| position.z).unwrap_or(index);
+                let stale = absolutes.get(entity).map_or(false, |absolute| absolute.z != z);
+                if stale {
+                    absolutes.get_mut(entity).unwrap().z = z;
+                }
+            }
+        }
     }
 }
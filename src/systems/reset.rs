@@ -0,0 +1,39 @@
+
+use specs::{System, WriteStorage};
+
+use class::{Bounds, Children, Display, Layout, LayoutItem, Name, Parent, Position, SafeArea, Text};
+
+/// Clears the change flags of the UI once everything has had a chance to read them.
+///
+/// Systems reading the flags have to run before this one, and systems changing the UI
+/// should run before the `PositionSystem` for it to be laid out in the same frame.
+/// `AbsolutePosition` is left alone, its flags are cleared by the `PositionSystem` when it
+/// lays out again.
+pub struct ResetSystem;
+impl<'a> System<'a> for ResetSystem {
+    type SystemData = (
+        WriteStorage<'a, Text>,
+        WriteStorage<'a, Layout>,
+        WriteStorage<'a, LayoutItem>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Bounds>,
+        WriteStorage<'a, Display>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, Children>,
+        WriteStorage<'a, Name>,
+        WriteStorage<'a, SafeArea>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut texts, mut layouts, mut items, mut positions, mut bounds, mut displays, mut parents, mut children, mut names, mut safe_areas) = data;
+        (&mut texts).open().1.clear_flags();
+        (&mut layouts).open().1.clear_flags();
+        (&mut items).open().1.clear_flags();
+        (&mut positions).open().1.clear_flags();
+        (&mut bounds).open().1.clear_flags();
+        (&mut displays).open().1.clear_flags();
+        (&mut parents).open().1.clear_flags();
+        (&mut children).open().1.clear_flags();
+        (&mut names).open().1.clear_flags();
+        (&mut safe_areas).open().1.clear_flags();
+    }
+}
@@ -1,13 +1,33 @@
 
 use std::marker::PhantomData;
 
-use specs::{self, Entity, Join, Index, UnprotectedStorage};
-use hibitset::{BitSet, BitSetLike};
+use specs::{DenseVecStorage, Entity, HashMapStorage, Join, Index, UnprotectedStorage, VecStorage};
+use hibitset::BitSet;
 
+/// Storage whose `get_mut` only ever touches the component at the given id.
+///
+/// Mutably joining over a `TrackStorage` hands out components that are all alive at the
+/// same time, which is only sound when getting one doesn't touch the others. Storages that
+/// move or look at other components in `get_mut` must not implement this.
+pub unsafe trait DistinctStorage<C>: UnprotectedStorage<C> { }
+
+unsafe impl<C> DistinctStorage<C> for DenseVecStorage<C> { }
+unsafe impl<C> DistinctStorage<C> for VecStorage<C> { }
+unsafe impl<C> DistinctStorage<C> for HashMapStorage<C> { }
+unsafe impl<C, T: DistinctStorage<C>> DistinctStorage<C> for TrackStorage<C, T> { }
+
+/// Storage that keeps track of which components were modified, inserted and removed,
+/// separately.
+///
+/// Joining over it goes through the modified components, which includes the inserted
+/// ones. Removed components are only known by their id, the entity might be dead by the
+/// time anything looks at them. Whoever consumes the flags is in charge of clearing them.
 pub struct TrackStorage<C, T> {
     mask: BitSet,
     inserted: BitSet,
     removed: BitSet,
+    /// Ids that have a component, the modified and inserted flags never go outside of it.
+    present: BitSet,
     storage: T,
     phantom: PhantomData<C>,
 }
@@ -18,6 +38,7 @@ impl<C, T: Default> Default for TrackStorage<C, T> {
             mask: BitSet::new(),
             inserted: BitSet::new(),
             removed: BitSet::new(),
+            present: BitSet::new(),
 
             storage: T::default(),
             phantom: PhantomData,
@@ -32,6 +53,7 @@ impl<C, T: UnprotectedStorage<C>> UnprotectedStorage<C> for TrackStorage<C, T> {
         self.mask.clear();
         self.inserted.clear();
         self.removed.clear();
+        self.present.clear();
         self.storage.clean(has);
     }
     unsafe fn get(&self, id: Index) -> &C {
@@ -43,21 +65,29 @@ impl<C, T: UnprotectedStorage<C>> UnprotectedStorage<C> for TrackStorage<C, T> {
         self.storage.get_mut(id)
     }
     unsafe fn insert(&mut self, id: Index, comp: C) {
+        // Taking a component out and putting one back in is just a change.
+        self.removed.remove(id);
         self.mask.add(id);
         self.inserted.add(id);
+        self.present.add(id);
         self.storage.insert(id, comp);
     }
     unsafe fn remove(&mut self, id: Index) -> C {
+        // Modified and inserted flags have to point at components that are there.
         self.mask.remove(id);
+        self.inserted.remove(id);
+        self.present.remove(id);
         self.removed.add(id);
         self.storage.remove(id)
     }
 }
 
 impl<C, T: UnprotectedStorage<C>> TrackStorage<C, T> {
+    /// Ids of the components inserted since the flags were cleared.
     pub fn inserted(&self) -> &BitSet {
         &self.inserted
     }
+    /// Ids of the components removed since the flags were cleared.
     pub fn removed(&self) -> &BitSet {
         &self.removed
     }
@@ -91,13 +121,18 @@ impl<C, T: UnprotectedStorage<C>> TrackStorage<C, T> {
     pub fn unflag_removed(&mut self, entity: Entity) {
         self.removed.remove(entity.id());
     }
-    /// Flags a single component as modified.
+    /// Flags a single component as modified, nothing happens if the entity doesn't have
+    /// one since joining over the storage goes through the modified flags.
     pub fn flag(&mut self, entity: Entity) {
-        self.mask.add(entity.id());
+        if self.present.contains(entity.id()) {
+            self.mask.add(entity.id());
+        }
     }
-    /// Flags a single component as inserted.
+    /// Flags a single component as inserted, see `flag`.
     pub fn flag_inserted(&mut self, entity: Entity) {
-        self.inserted.add(entity.id());
+        if self.present.contains(entity.id()) {
+            self.inserted.add(entity.id());
+        }
     }
     /// Flags a single component as removed.
     pub fn flag_removed(&mut self, entity: Entity) {
@@ -117,18 +152,20 @@ impl<'a, C, T: UnprotectedStorage<C>> Join for &'a TrackStorage<C, T> {
     }
 }
 
-impl<'a, C, T: UnprotectedStorage<C>> Join for &'a mut TrackStorage<C, T> {
+impl<'a, C: 'a, T: DistinctStorage<C> + 'a> Join for &'a mut TrackStorage<C, T> {
     type Type = &'a mut C;
-    type Value = &'a mut T;
+    // A pointer rather than `&'a mut T`, every `get` hands out a `&'a mut C` and they
+    // can't all be reborrowed from the same `&mut Self::Value`. The components never
+    // overlap since the mask yields every id once and `DistinctStorage` promises that
+    // `get_mut` keeps to the component at `id`.
+    type Value = *mut T;
     type Mask = &'a BitSet;
     fn open(self) -> (Self::Mask, Self::Value) {
-        (&self.mask, &mut self.storage)
+        (&self.mask, &mut self.storage as *mut T)
     }
     unsafe fn get(v: &mut Self::Value, id: Index) -> &'a mut C {
-        // similar issue here as the `Storage<T, A, D>` implementation
-        use std::mem;
-        let value: &'a mut Self::Value = mem::transmute(v);
-        value.get_mut(id)
+        let storage: &'a mut T = &mut **v;
+        storage.get_mut(id)
     }
 }
 
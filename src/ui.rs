@@ -1,14 +1,25 @@
 
+use std::any::Any;
+
 use specs::{Component, Entity, LazyUpdate, World};
 
-use class::{Layout, Text};
+use class::{Layout, LayoutItem, Name, SafeArea, Text};
+use hierarchy::set_parent;
 
 pub trait UiBuilder {
     fn with<C>(self, c: C) -> Self where C: Component + Send + Sync;
     fn with_id<C>(self, c: C, id: usize) -> Self where C: Component + Send + Sync;
     fn text(self, s: String) -> Self;
+    /// Names the entity so it can be found in the `name::NameIndex`.
+    fn name(self, name: &str) -> Self;
+    /// Parents every entity in `list` to this one, in order.
     fn children<'a>(self, list: &'a [Entity]) -> Self;
-    fn layout(self, layout: Box<Layout>) -> Self;
+    /// Parents this entity to `parent`.
+    fn parent(self, parent: Entity) -> Self;
+    fn layout<L>(self, layout: L) -> Self where L: Any + Send + Sync;
+    fn item<I>(self, item: I) -> Self where I: Any + Send + Sync;
+    /// Keeps the entity inside of the safe area of the `Viewport`, see `SafeArea`.
+    fn safe_area(self) -> Self;
 
     fn done(self) -> Entity;
 }
@@ -16,6 +27,8 @@ pub trait UiBuilder {
 pub trait InsertComponent {
     fn get_insert<T>(&mut self, value: T, id: usize)
         where T: Component + Send + Sync;
+    /// Gives `child` a `Parent` of `parent` and moves it into the parent's `Children`.
+    fn set_parent(&mut self, child: Entity, parent: Entity);
     fn entity(&self) -> Entity;
 }
 
@@ -39,6 +52,9 @@ impl<'a> InsertComponent for Ui<'a> {
     {
         self.world.write_with_id(id).insert(self.entity, value);
     }
+    fn set_parent(&mut self, child: Entity, parent: Entity) {
+        set_parent(self.world, child, parent);
+    }
     fn entity(&self) -> Entity {
         self.entity
     }
@@ -67,6 +83,11 @@ impl<'a> InsertComponent for LazyUi<'a> {
             world.write_with_id::<T>(id).insert(entity, value);
         })
     }
+    fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.lazy.execute(move |world| {
+            set_parent(world, child, parent);
+        })
+    }
     fn entity(&self) -> Entity {
         self.entity
     }
@@ -88,14 +109,39 @@ impl<T> UiBuilder for T
         self
     }
     fn text(mut self, string: String) -> Self {
-        self.get_insert::<Text>(Text { text: string, }, 0);
+        self.get_insert::<Text>(Text { text: string, size: 16.0, }, 0);
+        self
+    }
+    fn name(mut self, name: &str) -> Self {
+        self.get_insert::<Name>(Name(name.to_owned()), 0);
         self
     }
     fn children<'a>(mut self, children: &'a [Entity]) -> Self {
+        let entity = self.entity();
+        for &child in children {
+            self.set_parent(child, entity);
+        }
+        self
+    }
+    fn parent(mut self, parent: Entity) -> Self {
+        let entity = self.entity();
+        self.set_parent(entity, parent);
+        self
+    }
+    fn layout<L>(mut self, layout: L) -> Self
+        where L: Any + Send + Sync,
+    {
+        self.get_insert::<Layout>(Layout::new(layout), 0);
+        self
+    }
+    fn item<I>(mut self, item: I) -> Self
+        where I: Any + Send + Sync,
+    {
+        self.get_insert::<LayoutItem>(LayoutItem::new(item), 0);
         self
     }
-    fn layout(mut self, layout: Box<Layout>) -> Self {
-        self.get_insert::<Layout>(Layout(layout), 0);
+    fn safe_area(mut self) -> Self {
+        self.get_insert::<SafeArea>(SafeArea, 0);
         self
     }
     fn done(self) -> Entity {
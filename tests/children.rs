@@ -0,0 +1,138 @@
+
+extern crate citrine;
+extern crate specs;
+
+use specs::{Dispatcher, DispatcherBuilder, Entity, World};
+use citrine::class::{Children, Parent};
+use citrine::hierarchy;
+use citrine::systems::children::ChildrenSystem;
+
+fn world() -> World {
+    let mut world = World::new();
+    citrine::register(&mut world);
+    world
+}
+
+fn dispatcher<'a, 'b>(system: ChildrenSystem) -> Dispatcher<'a, 'b> {
+    DispatcherBuilder::new()
+        .add(system, "children", &[])
+        .build()
+}
+
+fn frame(world: &mut World, dispatcher: &mut Dispatcher) {
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+}
+
+fn children(world: &World, parent: Entity) -> Option<Vec<Entity>> {
+    world.read::<Children>().get(parent).map(|children| children.entities().clone())
+}
+
+fn child(world: &mut World, parent: Entity) -> Entity {
+    world.create_entity().with(Parent { entity: parent }).build()
+}
+
+#[test]
+fn children_are_only_added_once() {
+    let mut world = world();
+    let mut dispatcher = dispatcher(ChildrenSystem::new());
+    let parent = world.create_entity().build();
+    let first = child(&mut world, parent);
+    frame(&mut world, &mut dispatcher);
+
+    // Already in the `Children` by the time the system sees the `Parent`.
+    let second = world.create_entity().build();
+    hierarchy::set_parent(&mut world, second, parent);
+    frame(&mut world, &mut dispatcher);
+
+    // Setting the same `Parent` again isn't a new child.
+    world.write::<Parent>().insert(first, Parent { entity: parent });
+    frame(&mut world, &mut dispatcher);
+
+    assert_eq!(children(&world, parent), Some(vec![first, second]));
+}
+
+#[test]
+fn children_leave_with_their_parent() {
+    let mut world = world();
+    let mut dispatcher = dispatcher(ChildrenSystem::new());
+    let parent = world.create_entity().build();
+    let other = world.create_entity().build();
+    let moved = child(&mut world, parent);
+    let removed = child(&mut world, parent);
+    let deleted = child(&mut world, parent);
+    let kept = child(&mut world, parent);
+    frame(&mut world, &mut dispatcher);
+    assert_eq!(children(&world, parent), Some(vec![moved, removed, deleted, kept]));
+
+    world.write::<Parent>().insert(moved, Parent { entity: other });
+    world.write::<Parent>().remove(removed);
+    world.delete_entity(deleted);
+    world.maintain();
+    frame(&mut world, &mut dispatcher);
+
+    assert_eq!(children(&world, parent), Some(vec![kept]));
+    assert_eq!(children(&world, other), Some(vec![moved]));
+}
+
+#[test]
+fn parents_without_children_lose_their_children() {
+    let mut world = world();
+    let mut dispatcher = dispatcher(ChildrenSystem::new());
+    let parent = world.create_entity().build();
+    let only = child(&mut world, parent);
+    frame(&mut world, &mut dispatcher);
+    assert_eq!(children(&world, parent), Some(vec![only]));
+
+    world.write::<Parent>().remove(only);
+    frame(&mut world, &mut dispatcher);
+
+    assert_eq!(children(&world, parent), None);
+}
+
+#[test]
+fn children_of_the_same_frame_go_by_id() {
+    let mut world = world();
+    let mut dispatcher = dispatcher(ChildrenSystem::new());
+    let parent = world.create_entity().build();
+    let a = world.create_entity().build();
+    let b = world.create_entity().build();
+    let c = world.create_entity().build();
+    frame(&mut world, &mut dispatcher);
+
+    {
+        let mut parents = world.write::<Parent>();
+        parents.insert(c, Parent { entity: parent });
+        parents.insert(a, Parent { entity: parent });
+        parents.insert(b, Parent { entity: parent });
+    }
+    frame(&mut world, &mut dispatcher);
+    assert_eq!(children(&world, parent), Some(vec![a, b, c]));
+
+    // Later frames go to the end, whatever their id.
+    world.write::<Parent>().remove(a);
+    frame(&mut world, &mut dispatcher);
+    world.write::<Parent>().insert(a, Parent { entity: parent });
+    frame(&mut world, &mut dispatcher);
+    assert_eq!(children(&world, parent), Some(vec![b, c, a]));
+}
+
+#[test]
+fn orphans_are_despawned_with_everything_below_them() {
+    let mut world = world();
+    let mut dispatcher = dispatcher(ChildrenSystem::new().despawn_orphans(true));
+    let parent = world.create_entity().build();
+    let middle = child(&mut world, parent);
+    let leaf = child(&mut world, middle);
+    let unrelated = world.create_entity().build();
+    frame(&mut world, &mut dispatcher);
+
+    world.delete_entity(parent);
+    world.maintain();
+    frame(&mut world, &mut dispatcher);
+
+    let entities = world.entities();
+    assert!(!entities.is_alive(middle));
+    assert!(!entities.is_alive(leaf));
+    assert!(entities.is_alive(unrelated));
+}
@@ -0,0 +1,94 @@
+
+extern crate citrine;
+extern crate specs;
+
+use specs::{Entity, SystemData, World};
+use citrine::class::{Children, Parent};
+use citrine::hierarchy::{self, Hierarchy, HierarchyError};
+
+fn world() -> World {
+    let mut world = World::new();
+    citrine::register(&mut world);
+    world
+}
+
+fn child(world: &mut World, parent: Entity) -> Entity {
+    let child = world.create_entity().build();
+    hierarchy::set_parent(world, child, parent);
+    child
+}
+
+fn collect<I: Iterator<Item = Result<Entity, HierarchyError>>>(iter: I) -> Vec<Result<Entity, HierarchyError>> {
+    iter.collect()
+}
+
+#[test]
+fn walking_a_parent_loop_stops_with_an_error() {
+    let mut world = world();
+    let a = world.create_entity().build();
+    let b = world.create_entity().build();
+    let c = world.create_entity().build();
+    {
+        let mut parents = world.write::<Parent>();
+        parents.insert(a, Parent { entity: b });
+        parents.insert(b, Parent { entity: c });
+        parents.insert(c, Parent { entity: a });
+    }
+
+    let hierarchy = Hierarchy::fetch(&world.res, 0);
+    assert_eq!(collect(hierarchy.ancestors(a)), vec![Ok(b), Ok(c), Err(HierarchyError::Cycle(a))]);
+    assert_eq!(hierarchy.root(a), Err(HierarchyError::Cycle(a)));
+    assert_eq!(hierarchy.depth(b), Err(HierarchyError::Cycle(b)));
+}
+
+#[test]
+fn walking_a_children_loop_stops_with_an_error() {
+    let mut world = world();
+    let a = world.create_entity().build();
+    let b = child(&mut world, a);
+    hierarchy::set_parent(&mut world, a, b);
+
+    let hierarchy = Hierarchy::fetch(&world.res, 0);
+    assert_eq!(collect(hierarchy.descendants(a)), vec![Ok(b), Err(HierarchyError::Cycle(a))]);
+    assert_eq!(collect(hierarchy.descendants_breadth_first(a)), vec![Ok(b), Err(HierarchyError::Cycle(a))]);
+}
+
+#[test]
+fn descendants_follow_the_order_of_the_children() {
+    let mut world = world();
+    let root = world.create_entity().build();
+    let x = child(&mut world, root);
+    let y = child(&mut world, root);
+    let x1 = child(&mut world, x);
+    let x2 = child(&mut world, x);
+    let y1 = child(&mut world, y);
+
+    {
+        let hierarchy = Hierarchy::fetch(&world.res, 0);
+        assert_eq!(collect(hierarchy.descendants(root)), vec![Ok(x), Ok(x1), Ok(x2), Ok(y), Ok(y1)]);
+        assert_eq!(collect(hierarchy.descendants_breadth_first(root)), vec![Ok(x), Ok(y), Ok(x1), Ok(x2), Ok(y1)]);
+        assert_eq!(hierarchy.root(x2), Ok(root));
+        assert_eq!(hierarchy.depth(x2), Ok(2));
+    }
+
+    world.write::<Children>().get_mut(root).unwrap().move_to(0, y);
+    world.write::<Children>().get_mut(x).unwrap().move_before(x2, x1);
+
+    let hierarchy = Hierarchy::fetch(&world.res, 0);
+    assert_eq!(collect(hierarchy.descendants(root)), vec![Ok(y), Ok(y1), Ok(x), Ok(x2), Ok(x1)]);
+    assert_eq!(collect(hierarchy.descendants_breadth_first(root)), vec![Ok(y), Ok(x), Ok(y1), Ok(x2), Ok(x1)]);
+}
+
+#[test]
+fn siblings_leave_out_the_element_itself() {
+    let mut world = world();
+    let root = world.create_entity().build();
+    let a = child(&mut world, root);
+    let b = child(&mut world, root);
+    let c = child(&mut world, root);
+
+    let hierarchy = Hierarchy::fetch(&world.res, 0);
+    assert_eq!(hierarchy.siblings(a), vec![b, c]);
+    assert_eq!(hierarchy.siblings(b), vec![a, c]);
+    assert_eq!(hierarchy.siblings(root), Vec::<Entity>::new());
+}
@@ -0,0 +1,239 @@
+
+extern crate citrine;
+extern crate specs;
+
+use specs::{DispatcherBuilder, Entities, Entity, FetchMut, Join, ReadStorage, System, World};
+use citrine::class::{AbsolutePosition, Bounds, Coordinate, Parent, Position, PositionKind, Text, Viewport};
+use citrine::hierarchy;
+use citrine::layout::Rect;
+use citrine::systems::children::ChildrenSystem;
+use citrine::systems::position::PositionSystem;
+use citrine::systems::reset::ResetSystem;
+
+fn world() -> World {
+    let mut world = World::new();
+    citrine::register(&mut world);
+    world.add_resource(Viewport::new(800, 600));
+    world
+}
+
+fn root(world: &mut World) -> Entity {
+    world.create_entity()
+        .with(Position {
+            kind: PositionKind::Absolute,
+            .. Position::default()
+        })
+        .with(Bounds {
+            width: Some(Coordinate::Percent(1.0)),
+            height: Some(Coordinate::Percent(1.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default())
+        .build()
+}
+
+/// `Relative` element of 10 by 10 pixels under `parent`.
+fn square(world: &mut World, parent: Entity) -> Entity {
+    world.create_entity()
+        .with(Parent { entity: parent })
+        .with(Bounds {
+            width: Some(Coordinate::Pixel(10.0)),
+            height: Some(Coordinate::Pixel(10.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default())
+        .build()
+}
+
+fn border_box(world: &World, entity: Entity) -> Rect {
+    world.read::<AbsolutePosition>().get(entity).unwrap().border_box()
+}
+
+fn was_flagged(world: &World, entity: Entity) -> bool {
+    world.read::<AbsolutePosition>().open().1.was_flagged(entity)
+}
+
+#[test]
+fn changing_a_leaf_only_lays_out_what_it_affects() {
+    let mut world = world();
+    let screen = root(&mut world);
+    let column = world.create_entity()
+        .with(Parent { entity: screen })
+        .with(AbsolutePosition::default())
+        .build();
+    let label = world.create_entity()
+        .with(Parent { entity: column })
+        .with(Text {
+            text: "ab".to_owned(),
+            size: 10.0,
+        })
+        .with(AbsolutePosition::default())
+        .build();
+    let below = square(&mut world, screen);
+
+    let other = root(&mut world);
+    let unrelated = square(&mut world, other);
+
+    let mut dispatcher = citrine::dispatcher();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, column), Rect { x: 0.0, y: 0.0, width: 10.0, height: 12.0 });
+    assert_eq!(border_box(&world, below), Rect { x: 0.0, y: 12.0, width: 10.0, height: 10.0 });
+
+    world.write::<Text>().get_mut(label).unwrap().text = "ab\ncd".to_owned();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+
+    assert_eq!(border_box(&world, label), Rect { x: 0.0, y: 0.0, width: 10.0, height: 24.0 });
+    assert_eq!(border_box(&world, column), Rect { x: 0.0, y: 0.0, width: 10.0, height: 24.0 });
+    assert_eq!(border_box(&world, below), Rect { x: 0.0, y: 24.0, width: 10.0, height: 10.0 });
+    assert!(was_flagged(&world, label));
+    assert!(was_flagged(&world, column));
+    assert!(was_flagged(&world, below));
+
+    assert!(!was_flagged(&world, screen));
+    assert!(!was_flagged(&world, other));
+    assert!(!was_flagged(&world, unrelated));
+}
+
+#[test]
+fn resizing_a_parent_arranges_its_descendants() {
+    let mut world = world();
+    let screen = world.create_entity()
+        .with(Position {
+            kind: PositionKind::Absolute,
+            .. Position::default()
+        })
+        .with(Bounds {
+            width: Some(Coordinate::Pixel(100.0)),
+            height: Some(Coordinate::Pixel(100.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default())
+        .build();
+    let half = world.create_entity()
+        .with(Parent { entity: screen })
+        .with(Bounds {
+            width: Some(Coordinate::Percent(0.5)),
+            height: Some(Coordinate::Pixel(20.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default())
+        .build();
+    let full = world.create_entity()
+        .with(Parent { entity: half })
+        .with(Bounds {
+            width: Some(Coordinate::Percent(1.0)),
+            height: Some(Coordinate::Pixel(5.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default())
+        .build();
+
+    let mut dispatcher = citrine::dispatcher();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, half).width, 50.0);
+    assert_eq!(border_box(&world, full).width, 50.0);
+
+    world.write::<Bounds>().get_mut(screen).unwrap().width = Some(Coordinate::Pixel(300.0));
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+
+    assert_eq!(border_box(&world, screen).width, 300.0);
+    assert_eq!(border_box(&world, half), Rect { x: 0.0, y: 0.0, width: 150.0, height: 20.0 });
+    assert_eq!(border_box(&world, full), Rect { x: 0.0, y: 0.0, width: 150.0, height: 5.0 });
+    assert!(was_flagged(&world, half));
+    assert!(was_flagged(&world, full));
+}
+
+#[test]
+fn taking_a_child_away_flows_its_siblings_again() {
+    let mut world = world();
+    let screen = root(&mut world);
+    let first = square(&mut world, screen);
+    let second = square(&mut world, screen);
+    let third = square(&mut world, screen);
+    let other = root(&mut world);
+
+    let mut dispatcher = citrine::dispatcher();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, second).y, 10.0);
+    assert_eq!(border_box(&world, third).y, 20.0);
+
+    world.delete_entity(first);
+    world.maintain();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, second).y, 0.0);
+    assert_eq!(border_box(&world, third).y, 10.0);
+
+    hierarchy::set_parent(&mut world, second, other);
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, second).y, 0.0);
+    assert_eq!(border_box(&world, third).y, 0.0);
+    assert!(was_flagged(&world, third));
+}
+
+/// Elements whose `Position` or `Bounds` were flagged when `Reader` ran.
+#[derive(Default)]
+struct Seen(Vec<Entity>);
+
+struct Reader;
+impl<'a> System<'a> for Reader {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Bounds>,
+        FetchMut<'a, Seen>,
+    );
+    fn run(&mut self, (entities, positions, bounds, mut seen): Self::SystemData) {
+        for entity in (&*entities).join() {
+            if positions.open().1.was_flagged(entity) || bounds.open().1.was_flagged(entity) {
+                seen.0.push(entity);
+            }
+        }
+    }
+}
+
+#[test]
+fn flags_are_readable_after_laying_out() {
+    let mut world = world();
+    world.add_resource(Seen::default());
+    let screen = root(&mut world);
+    let moved = square(&mut world, screen);
+    let resized = square(&mut world, screen);
+    let still = square(&mut world, screen);
+
+    let mut dispatcher = DispatcherBuilder::new()
+        .add(ChildrenSystem::new(), "children", &[])
+        .add(PositionSystem::new(), "position", &["children"])
+        .add(Reader, "reader", &["position"])
+        .add(ResetSystem, "reset", &["reader"])
+        .build();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    world.write_resource::<Seen>().0.clear();
+
+    world.write::<Position>().insert(moved, Position {
+        x: Some(Coordinate::Pixel(5.0)),
+        .. Position::default()
+    });
+    world.write::<Bounds>().get_mut(resized).unwrap().width = Some(Coordinate::Pixel(30.0));
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+
+    assert_eq!(border_box(&world, moved).x, 5.0);
+    assert_eq!(border_box(&world, resized).width, 30.0);
+
+    let seen = world.read_resource::<Seen>();
+    assert!(seen.0.contains(&moved));
+    assert!(seen.0.contains(&resized));
+    assert!(!seen.0.contains(&still));
+
+    // Cleared by the `ResetSystem` once everyone had a look.
+    assert!(!world.read::<Position>().open().1.was_flagged(moved));
+    assert!(!world.read::<Bounds>().open().1.was_flagged(resized));
+}
@@ -0,0 +1,97 @@
+
+extern crate citrine;
+extern crate specs;
+
+use specs::World;
+use citrine::class::{AbsolutePosition, Bounds, Coordinate, Position, PositionKind, SafeArea, Viewport};
+use citrine::layout::{Insets, Rect};
+
+fn world(viewport: Viewport) -> World {
+    let mut world = World::new();
+    citrine::register(&mut world);
+    world.add_resource(viewport);
+    world
+}
+
+fn full_screen(world: &mut World, safe: bool) -> specs::Entity {
+    let builder = world.create_entity()
+        .with(Position {
+            kind: PositionKind::Absolute,
+            .. Position::default()
+        })
+        .with(Bounds {
+            width: Some(Coordinate::Percent(1.0)),
+            height: Some(Coordinate::Percent(1.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default());
+
+    if safe {
+        builder.with(SafeArea).build()
+    } else {
+        builder.build()
+    }
+}
+
+fn border_box(world: &World, entity: specs::Entity) -> Rect {
+    world.read::<AbsolutePosition>().get(entity).unwrap().border_box()
+}
+
+#[test]
+fn safe_area_roots_stay_inside_of_the_insets() {
+    // 500 by 250 logical pixels, with 20 by 10 of them cut off on each side.
+    let viewport = Viewport::new(1000, 500)
+        .with_scale_factor(2.0)
+        .with_safe_area(Insets {
+            top: 20.0,
+            bottom: 20.0,
+            left: 40.0,
+            right: 40.0,
+        });
+    let mut world = world(viewport.clone());
+
+    let safe = full_screen(&mut world, true);
+    let unsafe_root = full_screen(&mut world, false);
+    let label = world.create_entity()
+        .with(SafeArea)
+        .with(Position::default())
+        .with(Bounds {
+            width: Some(Coordinate::Pixel(100.0)),
+            height: Some(Coordinate::Pixel(50.0)),
+            .. Bounds::default()
+        })
+        .with(AbsolutePosition::default())
+        .build();
+
+    let mut dispatcher = citrine::dispatcher();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+
+    assert_eq!(border_box(&world, safe), Rect { x: 20.0, y: 10.0, width: 460.0, height: 230.0 });
+    assert_eq!(border_box(&world, unsafe_root), Rect { x: 0.0, y: 0.0, width: 500.0, height: 250.0 });
+    assert_eq!(border_box(&world, label), Rect { x: 20.0, y: 10.0, width: 100.0, height: 50.0 });
+
+    let physical = world.read::<AbsolutePosition>().get(safe).unwrap().physical(&viewport);
+    assert_eq!(physical, Rect { x: 40.0, y: 20.0, width: 920.0, height: 460.0 });
+}
+
+#[test]
+fn changing_the_insets_lays_out_again() {
+    let mut world = world(Viewport::new(800, 600));
+    let safe = full_screen(&mut world, true);
+
+    let mut dispatcher = citrine::dispatcher();
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, safe), Rect { x: 0.0, y: 0.0, width: 800.0, height: 600.0 });
+
+    world.write_resource::<Viewport>().safe_area = Insets {
+        top: 0.0,
+        bottom: 100.0,
+        left: 0.0,
+        right: 0.0,
+    };
+    dispatcher.dispatch(&mut world.res);
+    world.maintain();
+    assert_eq!(border_box(&world, safe), Rect { x: 0.0, y: 0.0, width: 800.0, height: 500.0 });
+}